@@ -0,0 +1,350 @@
+//! Generates the raw lexers' class tables and the marked sections of `spec/raw.md`
+//! from the class metadata in `spec/raw-classes.txt`.
+//!
+//! Writes `raw_classes.rs`, defining `raw::Class` and the `raw_classes!` macro, and `raw.md`,
+//! the regenerated spec that `tests/spec.rs` checks `spec/raw.md` against.
+
+use std::{env, fmt::Write as _, fs, path::Path};
+
+const METADATA: &str = "spec/raw-classes.txt";
+const SPEC: &str = "spec/raw.md";
+
+struct Metadata<'a> {
+    /// Groups of named subpatterns, as they are shown in the spec.
+    subpatterns: Vec<Vec<(&'a str, &'a str)>>,
+    regular: Vec<(&'a str, &'a str)>,
+    nonregular: Vec<(&'a str, &'a str)>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", METADATA);
+    println!("cargo:rerun-if-changed={}", SPEC);
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir);
+
+    let metadata = fs::read_to_string(METADATA).unwrap();
+    let metadata = parse(&metadata);
+    fs::write(out_dir.join("raw_classes.rs"), raw_classes(&metadata)).unwrap();
+
+    let mut spec = fs::read_to_string(SPEC).unwrap();
+    splice(&mut spec, "subpatterns", &spec_subpatterns(&metadata));
+    splice(&mut spec, "regular", &spec_classes(&metadata.regular));
+    splice(&mut spec, "nonregular", &spec_classes(&metadata.nonregular));
+    fs::write(out_dir.join("raw.md"), spec).unwrap();
+}
+
+fn parse(metadata: &str) -> Metadata<'_> {
+    let mut parsed = Metadata {
+        subpatterns: vec![vec![]],
+        regular: vec![],
+        nonregular: vec![],
+    };
+    for (line_number, line) in metadata.lines().enumerate() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match &*fields {
+            [] if !parsed.subpatterns.last().unwrap().is_empty() => parsed.subpatterns.push(vec![]),
+            [] => {}
+            ["subpattern", name, regex] => {
+                parsed.subpatterns.last_mut().unwrap().push((name, regex))
+            }
+            ["regular", name, regex] => parsed.regular.push((name, regex)),
+            ["nonregular", name, regex] => parsed.nonregular.push((name, regex)),
+            _ => panic!(
+                "{}:{}: malformed line {:?}",
+                METADATA,
+                line_number + 1,
+                line
+            ),
+        }
+    }
+    parsed.subpatterns.retain(|group| !group.is_empty());
+    parsed
+}
+
+/// Replaces each `(?&name)` in `regex` with the named subpattern, recursively.
+fn inline(regex: &str, metadata: &Metadata<'_>) -> String {
+    let mut inlined = String::new();
+    let mut rest = regex;
+    while let Some(at) = rest.find("(?&") {
+        inlined.push_str(&rest[..at]);
+        let end = at + rest[at..].find(')').unwrap();
+        let name = &rest[at + 3..end];
+        let subpattern = metadata
+            .subpatterns
+            .iter()
+            .flatten()
+            .find(|&&(subpattern, _)| subpattern == name)
+            .unwrap_or_else(|| panic!("{}: undefined subpattern {:?}", METADATA, name));
+        write!(inlined, "(?:{})", inline(subpattern.1, metadata)).unwrap();
+        rest = &rest[end + 1..];
+    }
+    inlined.push_str(rest);
+    inlined
+}
+
+/// Spells out each counted repetition `x{m}`, `x{m,}` or `x{m,n}` in `regex`
+/// with `x` repeated, as logos does not support them.
+fn expand_repetitions(regex: &str) -> String {
+    let mut expanded = String::new();
+    // Where the last atom starts in `expanded`, and where each open group starts.
+    let mut atom = 0;
+    let mut groups = vec![];
+    let mut chars = regex.chars().peekable();
+    while let Some(c) = chars.next() {
+        let start = expanded.len();
+        match c {
+            '\\' => {
+                expanded.push(c);
+                let escaped = chars.next().unwrap();
+                expanded.push(escaped);
+                if "pPx".contains(escaped) && chars.peek() == Some(&'{') {
+                    for c in &mut chars {
+                        expanded.push(c);
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+                atom = start;
+            }
+            '[' => {
+                expanded.push(c);
+                let mut depth = 1;
+                while depth > 0 {
+                    let c = chars.next().unwrap();
+                    expanded.push(c);
+                    match c {
+                        '\\' => expanded.push(chars.next().unwrap()),
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                }
+                atom = start;
+            }
+            '(' => {
+                expanded.push(c);
+                groups.push(start);
+            }
+            ')' => {
+                expanded.push(c);
+                atom = groups.pop().unwrap();
+            }
+            '{' => {
+                let mut bounds = String::new();
+                for c in &mut chars {
+                    if c == '}' {
+                        break;
+                    }
+                    bounds.push(c);
+                }
+                let x = expanded.split_off(atom);
+                let (min, max) = match bounds.find(',') {
+                    None => (bounds.parse().unwrap(), Some(bounds.parse().unwrap())),
+                    Some(at) if at + 1 == bounds.len() => (bounds[..at].parse().unwrap(), None),
+                    Some(at) => (
+                        bounds[..at].parse().unwrap(),
+                        Some(bounds[at + 1..].parse().unwrap()),
+                    ),
+                };
+                for _ in 0..min {
+                    expanded.push_str(&x);
+                }
+                match max {
+                    Some(max) => {
+                        for _ in min..max {
+                            write!(expanded, "{}?", x).unwrap();
+                        }
+                    }
+                    None => write!(expanded, "{}*", x).unwrap(),
+                }
+                atom = expanded.len();
+            }
+            _ => {
+                expanded.push(c);
+                atom = start;
+            }
+        }
+    }
+    expanded
+}
+
+/// Spells out each negated class `[^x]` in `regex` as its ASCII and non-ASCII parts,
+/// as logos matches a negated class that includes ASCII byte by byte,
+/// failing on the continuation bytes of a non-ASCII character.
+fn split_negated_classes(regex: &str) -> String {
+    let mut split = String::new();
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                split.push(c);
+                split.push(chars.next().unwrap());
+            }
+            '[' => {
+                let mut class = String::from(c);
+                let mut depth = 1;
+                while depth > 0 {
+                    let c = chars.next().unwrap();
+                    class.push(c);
+                    match c {
+                        '\\' => class.push(chars.next().unwrap()),
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                }
+                match class.strip_prefix("[^") {
+                    Some(negated) => {
+                        let negated = &negated[..negated.len() - 1];
+                        write!(
+                            split,
+                            r"(?:[^{0}\x{{80}}-\x{{10FFFF}}]|[^{0}\x00-\x7F])",
+                            negated,
+                        )
+                        .unwrap();
+                    }
+                    None => split.push_str(&class),
+                }
+            }
+            _ => split.push(c),
+        }
+    }
+    split
+}
+
+/// A raw string literal of `s`.
+fn literal(s: &str) -> String {
+    let mut hashes = String::new();
+    while s.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{}\"{}\"{}", hashes, s, hashes)
+}
+
+/// `regex` with its subpatterns inlined, rewritten to avoid what logos does not support.
+fn logos_compatible(regex: &str, metadata: &Metadata<'_>) -> String {
+    expand_repetitions(&split_negated_classes(&inline(regex, metadata)))
+}
+
+fn raw_classes(metadata: &Metadata<'_>) -> String {
+    let classes: Vec<&str> = metadata
+        .regular
+        .iter()
+        .chain(&metadata.nonregular)
+        .map(|&(name, _)| name)
+        .collect();
+    let mut out = String::new();
+    out.push_str(
+        "#[allow(nonstandard_style)]\n\
+         #[derive(Debug, Copy, Clone, Eq, PartialEq)]\n\
+         #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n\
+         pub enum Class {\n",
+    );
+    for name in &classes {
+        writeln!(out, "    {},", name).unwrap();
+    }
+    out.push_str("}\npub use Class::*;\n\nimpl Class {\n");
+    writeln!(out, "    pub const COUNT: usize = {};", classes.len()).unwrap();
+    out.push_str("    pub const ALL: [Class; Class::COUNT] = [\n");
+    for name in &classes {
+        writeln!(out, "        {},", name).unwrap();
+    }
+    out.push_str("    ];\n}\n\n");
+
+    out.push_str(
+        "/// Invokes `$then!` with every raw class in order, each as `name = regex / logos_regex;`,\n\
+         /// where `regex` has its subpatterns inlined and `logos_regex` is that rewritten for logos.\n\
+         /// The regex of a nonregular class is its regular prefix, and is followed by `=> recognizer`.\n\
+         macro_rules! raw_classes {\n    ($then:ident) => {\n        $then! {\n",
+    );
+    for (&(name, regex), nonregular) in metadata
+        .regular
+        .iter()
+        .map(|class| (class, false))
+        .chain(metadata.nonregular.iter().map(|class| (class, true)))
+    {
+        write!(
+            out,
+            "            {} = {} / {}",
+            name,
+            literal(&inline(regex, metadata)),
+            literal(&logos_compatible(regex, metadata)),
+        )
+        .unwrap();
+        if nonregular {
+            write!(out, " => parse_{}", name).unwrap();
+        }
+        out.push_str(";\n");
+    }
+    out.push_str("        }\n    };\n}\n");
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn spec_subpatterns(metadata: &Metadata<'_>) -> String {
+    let name_width = metadata
+        .subpatterns
+        .iter()
+        .flatten()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::from("```regex\n(?x)(?(DEFINE)\n");
+    for (i, group) in metadata.subpatterns.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let regex_width = group.iter().map(|(_, regex)| regex.len()).max().unwrap();
+        for (name, regex) in group {
+            let name = format!("(?P<{}>", name);
+            writeln!(
+                out,
+                "    {:name_width$} {:regex_width$} )",
+                name,
+                regex,
+                name_width = name_width + 5,
+                regex_width = regex_width,
+            )
+            .unwrap();
+        }
+    }
+    out.push_str(")\n```\n");
+    out
+}
+
+fn spec_classes(classes: &[(&str, &str)]) -> String {
+    let mut out = String::from("<dl>\n");
+    for (i, (name, regex)) in classes.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        writeln!(out, "  <dt><code>{}</code></dt>", name).unwrap();
+        writeln!(out, "  <dd><code>{}</code></dd>", escape_html(regex)).unwrap();
+    }
+    out.push_str("</dl>\n");
+    out
+}
+
+/// Replaces the lines between `<!-- generated: {section} -->` and `<!-- end generated -->`.
+fn splice(spec: &mut String, section: &str, generated: &str) {
+    let begin = format!("<!-- generated: {} -->\n", section);
+    let end = "<!-- end generated -->";
+    let start = spec
+        .find(&begin)
+        .unwrap_or_else(|| panic!("{}: missing {:?}", SPEC, begin))
+        + begin.len();
+    let stop = start
+        + spec[start..]
+            .find(end)
+            .unwrap_or_else(|| panic!("{}: unterminated {:?}", SPEC, begin));
+    spec.replace_range(start..stop, generated);
+}
@@ -0,0 +1,28 @@
+//! Feeds arbitrary UTF-8 to both raw lexers and fails on any difference in their output,
+//! reporting a minimal input on which they differ.
+//!
+//! Run with `cargo +nightly fuzz run raw_differential` from the repository root.
+
+#![no_main]
+
+use {
+    lexical_specification::{
+        minimize,
+        raw::{logos_impl, reference_impl},
+    },
+    libfuzzer_sys::fuzz_target,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(src) = std::str::from_utf8(data) {
+        let minimal = minimize::raw_disagreement::<reference_impl::Lexer, logos_impl::Lexer>(src);
+        if let Some(minimal) = minimal {
+            panic!(
+                "raw lexers disagree on {:?}, and minimally on {:?}; add it to tests/cases:\n{}",
+                src,
+                minimal,
+                minimize::test_case(&minimal),
+            );
+        }
+    }
+});
@@ -0,0 +1,330 @@
+//! Lexes every `.rs` file under the given directories with each raw lexer and cooker,
+//! reporting for each file whether they agree with the reference implementations,
+//! how many errors the reference found, and where each implementation first differs.
+//!
+//! Run with e.g. `cargo run --release --all-features --bin corpus -- path/to/rust/src`.
+//! Exits with failure if any implementation disagrees on any file.
+
+use {
+    lexical_specification::{
+        cooked::{self, Lexer as _},
+        line_index::{Encoding, LineIndex},
+        raw::{self, Lexer as _},
+    },
+    std::{
+        env, fmt, fs, io,
+        panic::{self, AssertUnwindSafe},
+        path::{Path, PathBuf},
+        process,
+    },
+};
+
+type ReferenceCooked = cooked::Pipeline<raw::reference_impl::Lexer, cooked::reference_impl::Cooker>;
+type RawLexAll = fn(&str) -> Vec<raw::RecoveredToken>;
+type CookedLexAll = fn(&str) -> Result<Vec<cooked::Token>, cooked::Error>;
+
+/// The raw lexers checked against the reference, by name.
+fn raw_lexers() -> Vec<(&'static str, RawLexAll)> {
+    vec![
+        #[cfg(feature = "logos")]
+        ("logos_impl", raw::logos_impl::Lexer::lex_all_recovering),
+    ]
+}
+
+/// The cooked lexers checked against the reference, by name.
+fn cooked_lexers() -> Vec<(&'static str, CookedLexAll)> {
+    vec![
+        ("rustc_impl", cooked::rustc_impl::Lexer::lex_all),
+        #[cfg(feature = "proc_macro2")]
+        (
+            "proc_macro2_impl",
+            cooked::Pipeline::<raw::reference_impl::Lexer, cooked::proc_macro2_impl::Cooker>::lex_all,
+        ),
+    ]
+}
+
+/// Where an implementation's output first differs from the reference's.
+struct Divergence {
+    /// Byte offset into the source.
+    offset: usize,
+    reference: String,
+    other: String,
+}
+
+/// Where two sequences of tokens which cover `src` first differ, if they do.
+fn first_difference<T: PartialEq>(
+    src: &str,
+    mut reference: impl Iterator<Item = T>,
+    mut other: impl Iterator<Item = T>,
+    end: impl Fn(&T) -> usize,
+    describe: impl Fn(&T) -> String,
+) -> Option<Divergence> {
+    let describe =
+        |token: Option<T>| token.map_or("the end of the source".to_string(), |t| describe(&t));
+    let mut offset = 0;
+    loop {
+        match (reference.next(), other.next()) {
+            (None, None) => return None,
+            (a, b) if a != b => {
+                return Some(Divergence {
+                    offset: offset.min(src.len()),
+                    reference: describe(a),
+                    other: describe(b),
+                })
+            }
+            (a, _) => offset = end(&a.unwrap()),
+        }
+    }
+}
+
+fn raw_divergence(
+    src: &str,
+    reference: &[raw::RecoveredToken],
+    other: &[raw::RecoveredToken],
+) -> Option<Divergence> {
+    first_difference(
+        src,
+        raw::Token::spanned(src, reference.iter().copied()),
+        raw::Token::spanned(src, other.iter().copied()),
+        raw::SpannedToken::end,
+        |token| match token.class {
+            Ok(class) => format!("{:?} {:?}", class, token.text),
+            Err(err) => format!("error {:?} ({})", token.text, err.kind),
+        },
+    )
+}
+
+fn cooked_divergence(
+    src: &str,
+    reference: &Result<Vec<cooked::Token>, cooked::Error>,
+    other: &Result<Vec<cooked::Token>, cooked::Error>,
+) -> Option<Divergence> {
+    let offset = |err: &cooked::Error| match err {
+        cooked::Error::Lex(err) => err.offset,
+        cooked::Error::Cook(err) => err.span.start,
+    };
+    match (reference, other) {
+        (Ok(reference), Ok(other)) => first_difference(
+            src,
+            cooked::Token::spanned(src, reference.iter().copied()),
+            cooked::Token::spanned(src, other.iter().copied()),
+            cooked::SpannedToken::end,
+            |token| format!("{:?} {:?}", token.class, token.text),
+        ),
+        _ if reference == other => None,
+        _ => {
+            let offset = [reference, other]
+                .iter()
+                .filter_map(|result| result.as_ref().err().map(offset))
+                .min()
+                .unwrap();
+            let describe = |result: &Result<Vec<cooked::Token>, cooked::Error>| match result {
+                Ok(tokens) => cooked::Token::spanned(src, tokens.iter().copied())
+                    .find(|token| token.end() > offset)
+                    .map_or("the end of the source".to_string(), |token| {
+                        format!("{:?} {:?}", token.class, token.text)
+                    }),
+                Err(err) => err.to_string(),
+            };
+            Some(Divergence {
+                offset,
+                reference: describe(reference),
+                other: describe(other),
+            })
+        }
+    }
+}
+
+/// Runs `lex`, turning a panic into a description of it.
+///
+/// The panic hook is silenced while `lex` runs, as the panic is reported per file instead.
+fn catch<T>(lex: impl FnOnce() -> T) -> Result<T, String> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(lex));
+    panic::set_hook(hook);
+    result.map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        format!("panicked: {}", message)
+    })
+}
+
+/// Counts kept across all files.
+#[derive(Default)]
+struct Summary {
+    files: usize,
+    unreadable: usize,
+    disagreeing_files: usize,
+    /// Files on which each implementation disagrees with the reference, by name.
+    disagreements: Vec<(&'static str, usize)>,
+    /// Reference raw tokens of each class, in the order of `raw::Class::ALL`, then skipped source.
+    tokens: Vec<usize>,
+    /// Divergences first found in a reference raw token of each class, indexed as `tokens`.
+    divergences: Vec<usize>,
+}
+
+impl Summary {
+    fn class_index(class: Result<raw::Class, raw::LexError>) -> usize {
+        class.map_or(raw::Class::COUNT, |class| class as usize)
+    }
+
+    fn disagreed(&mut self, name: &'static str) {
+        match self.disagreements.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, count)) => *count += 1,
+            None => self.disagreements.push((name, 1)),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} files, {} with disagreements, {} unreadable",
+            self.files, self.disagreeing_files, self.unreadable,
+        )?;
+        for (name, count) in &self.disagreements {
+            writeln!(f, "    {:<20} disagrees on {} files", name, count)?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<20} {:>10} {:>12}",
+            "raw class", "tokens", "divergences"
+        )?;
+        for i in 0..=raw::Class::COUNT {
+            let (tokens, divergences) = (self.tokens[i], self.divergences[i]);
+            if tokens > 0 || divergences > 0 {
+                let class = raw::Class::ALL
+                    .get(i)
+                    .map_or("error".to_string(), |class| format!("{:?}", class));
+                writeln!(f, "{:<20} {:>10} {:>12}", class, tokens, divergences)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_file(path: &Path, summary: &mut Summary) -> io::Result<()> {
+    let src = fs::read_to_string(path)?;
+    let index = LineIndex::new(&src);
+    let reference = raw::reference_impl::Lexer::lex_all_recovering(&src);
+    for token in &reference {
+        summary.tokens[Summary::class_index(token.class)] += 1;
+    }
+    let class_at = |offset: usize| {
+        raw::Token::spanned(&src, reference.iter().copied())
+            .find(|token| token.span().contains(&offset))
+            .map_or(raw::Class::COUNT, |token| Summary::class_index(token.class))
+    };
+
+    let reference_cooked = ReferenceCooked::lex_all(&src);
+    let mut divergences = vec![];
+    for (name, lex_all) in raw_lexers() {
+        let divergence = catch(|| lex_all(&src))
+            .map(|other| raw_divergence(&src, &reference, &other))
+            .transpose();
+        divergences.extend(divergence.map(|divergence| (name, divergence)));
+    }
+    for (name, lex_all) in cooked_lexers() {
+        let divergence = catch(|| lex_all(&src))
+            .map(|other| cooked_divergence(&src, &reference_cooked, &other))
+            .transpose();
+        divergences.extend(divergence.map(|divergence| (name, divergence)));
+    }
+
+    let cooking = match &reference_cooked {
+        Ok(tokens) => format!("{} cooked tokens", tokens.len()),
+        Err(err) => format!("cooking failed: {}", err),
+    };
+    println!(
+        "{} {}: {} raw tokens, {} lex errors, {}",
+        if divergences.is_empty() {
+            "agree   "
+        } else {
+            "DISAGREE"
+        },
+        path.display(),
+        reference.len(),
+        reference
+            .iter()
+            .filter(|token| token.class.is_err())
+            .count(),
+        cooking,
+    );
+    if !divergences.is_empty() {
+        summary.disagreeing_files += 1;
+    }
+    for (name, divergence) in divergences {
+        summary.disagreed(name);
+        match divergence {
+            Ok(divergence) => {
+                summary.divergences[class_at(divergence.offset)] += 1;
+                let pos = index.line_col(divergence.offset, Encoding::Char).unwrap();
+                println!(
+                    "    {} first differs at {}:{}: reference has {}, {} has {}",
+                    name,
+                    pos.line + 1,
+                    pos.col + 1,
+                    divergence.reference,
+                    name,
+                    divergence.other,
+                );
+            }
+            Err(panic) => println!("    {} {}", name, panic),
+        }
+    }
+    Ok(())
+}
+
+/// The `.rs` files under `dir`, in order, not following symlinks.
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            rust_files(&path, files)?;
+        } else if file_type.is_file() && path.extension() == Some("rs".as_ref()) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let dirs: Vec<PathBuf> = env::args_os().skip(1).map(PathBuf::from).collect();
+    if dirs.is_empty() {
+        eprintln!("usage: corpus DIR...");
+        process::exit(2);
+    }
+    let mut summary = Summary {
+        tokens: vec![0; raw::Class::COUNT + 1],
+        divergences: vec![0; raw::Class::COUNT + 1],
+        ..Summary::default()
+    };
+    for dir in &dirs {
+        let mut files = vec![];
+        if let Err(err) = rust_files(dir, &mut files) {
+            eprintln!("{}: {}", dir.display(), err);
+            process::exit(2);
+        }
+        for path in files {
+            summary.files += 1;
+            if let Err(err) = check_file(&path, &mut summary) {
+                summary.unreadable += 1;
+                println!("skipped  {}: {}", path.display(), err);
+            }
+        }
+    }
+    println!();
+    print!("{}", summary);
+    if summary.disagreeing_files > 0 {
+        process::exit(1);
+    }
+}
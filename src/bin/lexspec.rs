@@ -0,0 +1,310 @@
+//! Lexes a file, or standard input, and prints its raw or cooked tokens
+//! with their spans and source text.
+//!
+//! Run with e.g. `cargo run --bin lexspec -- --cook --format json src/lib.rs`,
+//! or `--all-features` to also lex with `--lexer logos`.
+//! With `--explain`, instead prints the rules of the spec by which the reference lexer
+//! and cooker produce each token.
+//! Exits with failure if lexing fails anywhere.
+
+use {
+    lexical_specification::{
+        cooked::{self, Lexer as _},
+        explain,
+        raw::{self, Lexer as _},
+    },
+    std::{
+        env, fs,
+        io::{self, Read},
+        ops::Range,
+        process,
+    },
+};
+
+const USAGE: &str = "usage: lexspec [--lexer reference|logos] [--cook] \
+                     [--format table|json|debug | --explain] [FILE]";
+
+type RawLexAll = fn(&str) -> Vec<raw::RecoveredToken>;
+type CookedLexAll = fn(&str) -> Result<Vec<cooked::Token>, cooked::Error>;
+
+/// The raw lexer named `name`, alone and followed by the reference cooker.
+fn lexer(name: &str) -> Result<(RawLexAll, CookedLexAll), String> {
+    match name {
+        "reference" => Ok((
+            raw::reference_impl::Lexer::lex_all_recovering,
+            cooked::Pipeline::<raw::reference_impl::Lexer, cooked::reference_impl::Cooker>::lex_all,
+        )),
+        #[cfg(feature = "logos")]
+        "logos" => Ok((
+            raw::logos_impl::Lexer::lex_all_recovering,
+            cooked::Pipeline::<raw::logos_impl::Lexer, cooked::reference_impl::Cooker>::lex_all,
+        )),
+        #[cfg(not(feature = "logos"))]
+        "logos" => Err("lexspec was built without the logos feature".to_string()),
+        _ => Err(format!("no lexer {:?}; expected reference or logos", name)),
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Format {
+    Table,
+    /// One JSON object per line, with the span and text of each token.
+    ///
+    /// This is deliberately not the shape of the `serde` feature's tokens,
+    /// which have a length and no text, as it is meant for reading a single file;
+    /// the class names are the same.
+    Json,
+    Debug,
+    Explain,
+}
+
+struct Options {
+    lexer: (RawLexAll, CookedLexAll),
+    cook: bool,
+    format: Format,
+    path: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        lexer: lexer("reference")?,
+        cook: false,
+        format: Format::Table,
+        path: None,
+    };
+    let mut reference = true;
+    let (mut formatted, mut explain) = (false, false);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match &*arg {
+            "--lexer" => {
+                let name = value()?;
+                options.lexer = lexer(&name)?;
+                reference = name == "reference";
+            }
+            "--cook" => options.cook = true,
+            "--format" => {
+                formatted = true;
+                options.format = match &*value()? {
+                    "table" => Format::Table,
+                    "json" => Format::Json,
+                    "debug" => Format::Debug,
+                    format => Err(format!(
+                        "no format {:?}; expected table, json or debug",
+                        format
+                    ))?,
+                }
+            }
+            "--explain" => explain = true,
+            "-h" | "--help" => Err(USAGE)?,
+            "-" if options.path.is_none() => options.path = Some(arg),
+            _ if arg.starts_with('-') => Err(format!("unknown option {}", arg))?,
+            _ if options.path.is_none() => options.path = Some(arg),
+            _ => Err("more than one FILE")?,
+        }
+    }
+    if explain {
+        if formatted {
+            Err("--explain has its own format, so takes no --format")?
+        }
+        if !reference {
+            Err("--explain explains the reference lexer only")?
+        }
+        options.format = Format::Explain;
+    }
+    Ok(options)
+}
+
+/// One token as printed.
+struct Row<'src> {
+    span: Range<usize>,
+    class: String,
+    text: &'src str,
+    /// How many raw tokens a cooked token was cooked from.
+    raw_tokens: Option<usize>,
+    /// Why lexing failed, for an `error` token.
+    error: Option<String>,
+    debug: String,
+}
+
+fn raw_rows(src: &str, tokens: Vec<raw::RecoveredToken>) -> Vec<Row<'_>> {
+    raw::Token::spanned(src, tokens)
+        .map(|token| match token.class {
+            Ok(class) => Row {
+                span: token.span(),
+                class: format!("{:?}", class),
+                text: token.text,
+                raw_tokens: None,
+                error: None,
+                debug: format!(
+                    "{:?}",
+                    raw::SpannedToken {
+                        class,
+                        start: token.start,
+                        text: token.text,
+                    }
+                ),
+            },
+            Err(err) => Row {
+                span: token.span(),
+                class: "error".to_string(),
+                text: token.text,
+                raw_tokens: None,
+                error: Some(err.kind.to_string()),
+                debug: format!("{:?}", token),
+            },
+        })
+        .collect()
+}
+
+fn cooked_rows(src: &str, tokens: Vec<cooked::Token>) -> Vec<Row<'_>> {
+    cooked::Token::spanned(src, tokens)
+        .map(|token| Row {
+            span: token.span(),
+            class: token.class.to_string(),
+            text: token.text,
+            raw_tokens: Some(token.raw_tokens),
+            error: None,
+            debug: format!("{:?}", token),
+        })
+        .collect()
+}
+
+/// `s` as a JSON string, as lexspec does not depend on `serde_json`.
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' || c == '\u{7f}' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn print(format: Format, rows: &[Row<'_>]) {
+    match format {
+        Format::Table => {
+            let spans: Vec<String> = rows.iter().map(|row| format!("{:?}", row.span)).collect();
+            let span_width = spans.iter().map(String::len).max().unwrap_or(0);
+            let class_width = rows.iter().map(|row| row.class.len()).max().unwrap_or(0);
+            for (row, span) in rows.iter().zip(&spans) {
+                let line = format!(
+                    "{:<span_width$}  {:<class_width$}  {:?}  {}",
+                    span,
+                    row.class,
+                    row.text,
+                    row.error.as_deref().unwrap_or(""),
+                    span_width = span_width,
+                    class_width = class_width,
+                );
+                println!("{}", line.trim_end());
+            }
+        }
+        Format::Json => {
+            for row in rows {
+                let mut json = format!(
+                    "{{\"start\":{},\"end\":{},\"class\":{},\"text\":{}",
+                    row.span.start,
+                    row.span.end,
+                    json_string(&row.class),
+                    json_string(row.text),
+                );
+                if let Some(raw_tokens) = row.raw_tokens {
+                    json.push_str(&format!(",\"raw_tokens\":{}", raw_tokens));
+                }
+                if let Some(error) = &row.error {
+                    json.push_str(&format!(",\"error\":{}", json_string(error)));
+                }
+                println!("{}}}", json);
+            }
+        }
+        Format::Debug => {
+            for row in rows {
+                println!("{}", row.debug);
+            }
+        }
+        Format::Explain => unreachable!("explanations are printed by explain"),
+    }
+}
+
+/// Prints how the reference lexer, and cooker if `cook`, produce each token of `src`,
+/// returning whether lexing succeeded.
+fn explain(src: &str, cook: bool) -> bool {
+    if !cook {
+        let explanations = explain::raw(src);
+        for explanation in &explanations {
+            println!("{}", explanation);
+        }
+        return explanations
+            .iter()
+            .all(|explanation| explanation.token.class.is_ok());
+    }
+    match explain::cooked(src) {
+        Ok(explanations) => {
+            for explanation in &explanations {
+                println!("{}", explanation);
+            }
+            explanations
+                .iter()
+                .all(|explanation| explanation.token.is_ok())
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            false
+        }
+    }
+}
+
+fn read_source(path: Option<&str>) -> io::Result<String> {
+    match path {
+        None | Some("-") => {
+            let mut src = String::new();
+            io::stdin().read_to_string(&mut src)?;
+            Ok(src)
+        }
+        Some(path) => fs::read_to_string(path),
+    }
+}
+
+fn main() {
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        if err != USAGE {
+            eprintln!("{}", USAGE);
+        }
+        process::exit(2);
+    });
+    let src = read_source(options.path.as_deref()).unwrap_or_else(|err| {
+        eprintln!("{}: {}", options.path.as_deref().unwrap_or("stdin"), err);
+        process::exit(2);
+    });
+
+    let (raw_lex_all, cooked_lex_all) = options.lexer;
+    if let Format::Explain = options.format {
+        if !explain(&src, options.cook) {
+            process::exit(1);
+        }
+    } else if options.cook {
+        match cooked_lex_all(&src) {
+            Ok(tokens) => print(options.format, &cooked_rows(&src, tokens)),
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+    } else {
+        let lexed = raw_lex_all(&src);
+        let failed = lexed.iter().any(|token| token.class.is_err());
+        print(options.format, &raw_rows(&src, lexed));
+        if failed {
+            process::exit(1);
+        }
+    }
+}
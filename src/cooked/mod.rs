@@ -1,4 +1,15 @@
+#[cfg(feature = "proc_macro2")]
+pub mod proc_macro2_impl;
+pub mod reference_impl;
+pub mod rustc_impl;
+
+use {
+    crate::raw,
+    std::{fmt, marker::PhantomData, ops::Range},
+};
+
 #[allow(nonstandard_style)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Class {
     Documentation_Inner_Block,
     Documentation_Inner_Line,
@@ -60,12 +71,473 @@ pub enum Class {
     Trivia_Comment_Line,
     Trivia_Whitespace,
 }
+pub use Class::*;
+
+impl Class {
+    pub const COUNT: usize = Trivia_Whitespace as usize + 1;
+    pub const ALL: [Class; Class::COUNT] = [
+        Documentation_Inner_Block,
+        Documentation_Inner_Line,
+        Documentation_Outer_Block,
+        Documentation_Outer_Line,
+        Identifier_Plain,
+        Identifier_Raw,
+        Lifetime,
+        Literal_ByteString_Suffixed,
+        Literal_ByteString_Unsuffixed,
+        Literal_Byte_Suffixed,
+        Literal_Byte_Unsuffixed,
+        Literal_Character_Suffixed,
+        Literal_Character_Unsuffixed,
+        Literal_Float_Suffixed,
+        Literal_Float_Unsuffixed,
+        Literal_Integer_Binary_Suffixed,
+        Literal_Integer_Binary_Unsuffixed,
+        Literal_Integer_Decimal_Suffixed,
+        Literal_Integer_Decimal_Unsuffixed,
+        Literal_Integer_Hexadecimal_Suffixed,
+        Literal_Integer_Hexadecimal_Unsuffixed,
+        Literal_Integer_Octal_Suffixed,
+        Literal_Integer_Octal_Unsuffixed,
+        Literal_RawByteString_Suffixed,
+        Literal_RawByteString_Unsuffixed,
+        Literal_RawString_Suffixed,
+        Literal_RawString_Unsuffixed,
+        Literal_String_Suffixed,
+        Literal_String_Unsuffixed,
+        Punctuation_Ampersand,
+        Punctuation_At,
+        Punctuation_Bar,
+        Punctuation_Brace_Close,
+        Punctuation_Brace_Open,
+        Punctuation_Bracket_Close,
+        Punctuation_Bracket_Open,
+        Punctuation_Circumflex,
+        Punctuation_Colon,
+        Punctuation_Comma,
+        Punctuation_Dollar,
+        Punctuation_Dot,
+        Punctuation_Equal,
+        Punctuation_Exclamation,
+        Punctuation_Greater,
+        Punctuation_Less,
+        Punctuation_Minus,
+        Punctuation_Parenthesis_Close,
+        Punctuation_Parenthesis_Open,
+        Punctuation_Percent,
+        Punctuation_Plus,
+        Punctuation_Pound,
+        Punctuation_Question,
+        Punctuation_Semicolon,
+        Punctuation_Slash,
+        Punctuation_Star,
+        Punctuation_Tilde,
+        Trivia_Comment_Block,
+        Trivia_Comment_Line,
+        Trivia_Whitespace,
+    ];
+
+    /// Each class as the specification spells it, in the order of [`Class::ALL`].
+    const NAMES: [&str; Class::COUNT] = [
+        "Documentation::Inner::Block",
+        "Documentation::Inner::Line",
+        "Documentation::Outer::Block",
+        "Documentation::Outer::Line",
+        "Identifier::Plain",
+        "Identifier::Raw",
+        "Lifetime",
+        "Literal::ByteString::Suffixed",
+        "Literal::ByteString::Unsuffixed",
+        "Literal::Byte::Suffixed",
+        "Literal::Byte::Unsuffixed",
+        "Literal::Character::Suffixed",
+        "Literal::Character::Unsuffixed",
+        "Literal::Float::Suffixed",
+        "Literal::Float::Unsuffixed",
+        "Literal::Integer::Binary::Suffixed",
+        "Literal::Integer::Binary::Unsuffixed",
+        "Literal::Integer::Decimal::Suffixed",
+        "Literal::Integer::Decimal::Unsuffixed",
+        "Literal::Integer::Hexadecimal::Suffixed",
+        "Literal::Integer::Hexadecimal::Unsuffixed",
+        "Literal::Integer::Octal::Suffixed",
+        "Literal::Integer::Octal::Unsuffixed",
+        "Literal::RawByteString::Suffixed",
+        "Literal::RawByteString::Unsuffixed",
+        "Literal::RawString::Suffixed",
+        "Literal::RawString::Unsuffixed",
+        "Literal::String::Suffixed",
+        "Literal::String::Unsuffixed",
+        "Punctuation::Ampersand",
+        "Punctuation::At",
+        "Punctuation::Bar",
+        "Punctuation::Brace::Close",
+        "Punctuation::Brace::Open",
+        "Punctuation::Bracket::Close",
+        "Punctuation::Bracket::Open",
+        "Punctuation::Circumflex",
+        "Punctuation::Colon",
+        "Punctuation::Comma",
+        "Punctuation::Dollar",
+        "Punctuation::Dot",
+        "Punctuation::Equal",
+        "Punctuation::Exclamation",
+        "Punctuation::Greater",
+        "Punctuation::Less",
+        "Punctuation::Minus",
+        "Punctuation::Parenthesis::Close",
+        "Punctuation::Parenthesis::Open",
+        "Punctuation::Percent",
+        "Punctuation::Plus",
+        "Punctuation::Pound",
+        "Punctuation::Question",
+        "Punctuation::Semicolon",
+        "Punctuation::Slash",
+        "Punctuation::Star",
+        "Punctuation::Tilde",
+        "Trivia::Comment::Block",
+        "Trivia::Comment::Line",
+        "Trivia::Whitespace",
+    ];
+
+    fn name(self) -> &'static str {
+        Class::NAMES[self as usize]
+    }
+}
+
+/// Formats the class as the specification spells it, such as `Literal::Float::Suffixed`.
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Serializes as the specification spells the class.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Class {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Class {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Class::NAMES
+            .iter()
+            .position(|known| *known == name)
+            .map(|i| Class::ALL[i])
+            .ok_or_else(|| serde::de::Error::custom(format!("no cooked class {:?}", name)))
+    }
+}
+
+/// The kind of literal named by the second segment of a `Literal::*` class.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LiteralKind {
+    ByteString,
+    Byte,
+    Character,
+    Float,
+    Integer(Base),
+    RawByteString,
+    RawString,
+    String,
+}
+
+/// The base of an integer literal.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Base {
+    Binary,
+    Decimal,
+    Hexadecimal,
+    Octal,
+}
+
+/// Whether a doc comment documents its parent (`//!`, `/*!`) or the following item (`///`, `/**`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DocStyle {
+    Inner,
+    Outer,
+}
+
+impl Class {
+    /// `Literal::*`.
+    pub fn is_literal(self) -> bool {
+        self.literal_kind().is_some()
+    }
+
+    pub fn literal_kind(self) -> Option<LiteralKind> {
+        Some(match self {
+            Literal_ByteString_Suffixed | Literal_ByteString_Unsuffixed => LiteralKind::ByteString,
+            Literal_Byte_Suffixed | Literal_Byte_Unsuffixed => LiteralKind::Byte,
+            Literal_Character_Suffixed | Literal_Character_Unsuffixed => LiteralKind::Character,
+            Literal_Float_Suffixed | Literal_Float_Unsuffixed => LiteralKind::Float,
+            Literal_Integer_Binary_Suffixed | Literal_Integer_Binary_Unsuffixed => {
+                LiteralKind::Integer(Base::Binary)
+            }
+            Literal_Integer_Decimal_Suffixed | Literal_Integer_Decimal_Unsuffixed => {
+                LiteralKind::Integer(Base::Decimal)
+            }
+            Literal_Integer_Hexadecimal_Suffixed | Literal_Integer_Hexadecimal_Unsuffixed => {
+                LiteralKind::Integer(Base::Hexadecimal)
+            }
+            Literal_Integer_Octal_Suffixed | Literal_Integer_Octal_Unsuffixed => {
+                LiteralKind::Integer(Base::Octal)
+            }
+            Literal_RawByteString_Suffixed | Literal_RawByteString_Unsuffixed => {
+                LiteralKind::RawByteString
+            }
+            Literal_RawString_Suffixed | Literal_RawString_Unsuffixed => LiteralKind::RawString,
+            Literal_String_Suffixed | Literal_String_Unsuffixed => LiteralKind::String,
+            _ => return None,
+        })
+    }
+
+    /// `Literal::*::Suffixed`.
+    pub fn is_suffixed(self) -> bool {
+        matches!(
+            self,
+            Literal_ByteString_Suffixed
+                | Literal_Byte_Suffixed
+                | Literal_Character_Suffixed
+                | Literal_Float_Suffixed
+                | Literal_Integer_Binary_Suffixed
+                | Literal_Integer_Decimal_Suffixed
+                | Literal_Integer_Hexadecimal_Suffixed
+                | Literal_Integer_Octal_Suffixed
+                | Literal_RawByteString_Suffixed
+                | Literal_RawString_Suffixed
+                | Literal_String_Suffixed
+        )
+    }
+
+    /// `Documentation::*`.
+    pub fn is_doc(self) -> bool {
+        self.doc_style().is_some()
+    }
+
+    pub fn doc_style(self) -> Option<DocStyle> {
+        match self {
+            Documentation_Inner_Block | Documentation_Inner_Line => Some(DocStyle::Inner),
+            Documentation_Outer_Block | Documentation_Outer_Line => Some(DocStyle::Outer),
+            _ => None,
+        }
+    }
 
+    /// The `Punctuation::*` class of a single char.
+    pub fn punctuation(c: char) -> Option<Class> {
+        Some(match c {
+            '!' => Punctuation_Exclamation,
+            '#' => Punctuation_Pound,
+            '$' => Punctuation_Dollar,
+            '%' => Punctuation_Percent,
+            '&' => Punctuation_Ampersand,
+            '(' => Punctuation_Parenthesis_Open,
+            ')' => Punctuation_Parenthesis_Close,
+            '*' => Punctuation_Star,
+            '+' => Punctuation_Plus,
+            ',' => Punctuation_Comma,
+            '-' => Punctuation_Minus,
+            '.' => Punctuation_Dot,
+            '/' => Punctuation_Slash,
+            ':' => Punctuation_Colon,
+            ';' => Punctuation_Semicolon,
+            '<' => Punctuation_Less,
+            '=' => Punctuation_Equal,
+            '>' => Punctuation_Greater,
+            '?' => Punctuation_Question,
+            '@' => Punctuation_At,
+            '[' => Punctuation_Bracket_Open,
+            ']' => Punctuation_Bracket_Close,
+            '^' => Punctuation_Circumflex,
+            '{' => Punctuation_Brace_Open,
+            '|' => Punctuation_Bar,
+            '}' => Punctuation_Brace_Close,
+            '~' => Punctuation_Tilde,
+            _ => return None,
+        })
+    }
+
+    /// `Trivia::*`: whitespace and comments which are not documentation.
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            Trivia_Comment_Block | Trivia_Comment_Line | Trivia_Whitespace
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
-    class: Class,
-    length: usize,
+    pub class: Class,
+    pub length: usize,
+    /// How many raw tokens this token was cooked from.
+    pub raw_tokens: usize,
+}
+
+impl Token {
+    /// Places `tokens`, which were cooked from the start of `src`, back into `src`.
+    pub fn spanned<'src>(
+        src: &'src str,
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> impl Iterator<Item = SpannedToken<'src>> {
+        let mut start = 0;
+        tokens.into_iter().map(move |token| {
+            let spanned = SpannedToken {
+                class: token.class,
+                start,
+                text: &src[start..start + token.length],
+                raw_tokens: token.raw_tokens,
+            };
+            start += token.length;
+            spanned
+        })
+    }
+}
+
+/// A cooked token along with where it is in the source.
+///
+/// The span covers all of the raw tokens the cooked token was made from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SpannedToken<'src> {
+    pub class: Class,
+    /// Byte offset of the start of the token in the source.
+    pub start: usize,
+    /// The source text of the token.
+    pub text: &'src str,
+    /// How many raw tokens this token was cooked from.
+    pub raw_tokens: usize,
 }
 
+impl SpannedToken<'_> {
+    /// Byte offset of the end of the token in the source.
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end()
+    }
+}
+
+/// Why the lexer cooker produced `ERROR`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CookError {
+    pub kind: CookErrorKind,
+    /// Byte span in the source of the raw tokens which caused the error.
+    pub span: Range<usize>,
+}
+
+/// Why cooking failed: no case matched, one of the reasons given for an `ERROR`
+/// in the specification, or a failure of the implementation doing the cooking.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CookErrorKind {
+    /// No case matches the front of the raw token stream.
+    NoMatchingCase,
+    /// An implementation failed in a way the specification has no `ERROR` for,
+    /// such as its own lexer rejecting the source; says how.
+    Implementation(&'static str),
+    InvalidIdentifierStart,
+    MultipleCodepointsInCharacter,
+    BinaryFloat,
+    OctalFloat,
+    HexadecimalFloat,
+    EmptyExponent,
+    EmptyBinaryInteger,
+    InvalidBinaryDigit,
+    EmptyOctalInteger,
+    InvalidOctalDigit,
+    EmptyHexadecimalInteger,
+}
+pub use CookErrorKind::*;
+
+impl CookError {
+    pub fn new(kind: CookErrorKind, span: Range<usize>) -> Self {
+        CookError { kind, span }
+    }
+}
+
+impl fmt::Display for CookErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NoMatchingCase => "No cooking case matches",
+            Implementation(how) => how,
+            InvalidIdentifierStart => "Invalid start to identifier",
+            MultipleCodepointsInCharacter => "character literal may only contain one codepoint",
+            BinaryFloat => "Binary float literals not supported",
+            OctalFloat => "Octal float literals not supported",
+            HexadecimalFloat => "Hexadecimal float literals not supported",
+            EmptyExponent => "No digits in float literal exponent",
+            EmptyBinaryInteger => "No digits in binary integer literal",
+            InvalidBinaryDigit => "Invalid digit for binary integer literal",
+            EmptyOctalInteger => "No digits in octal integer literal",
+            InvalidOctalDigit => "Invalid digit for octal integer literal",
+            EmptyHexadecimalInteger => "No digits in hexadecimal integer literal",
+        })
+    }
+}
+
+impl fmt::Display for CookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at bytes {:?}", self.kind, self.span)
+    }
+}
+
+impl std::error::Error for CookError {}
+
+/// Why source text could not be lexed into cooked tokens.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    Lex(raw::LexError),
+    Cook(CookError),
+}
+
+impl From<raw::LexError> for Error {
+    fn from(err: raw::LexError) -> Self {
+        Error::Lex(err)
+    }
+}
+
+impl From<CookError> for Error {
+    fn from(err: CookError) -> Self {
+        Error::Cook(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Lex(err) => err.fmt(f),
+            Error::Cook(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 pub trait Cooker {
-    fn cook_all(raw: &[crate::raw::Token]) -> Result<Vec<Token>, ()>;
+    /// Cooks `raw`, the raw tokens lexed from all of `src`.
+    fn cook_all(src: &str, raw: &[raw::Token]) -> Result<Vec<Token>, CookError>;
+}
+
+/// Lexes source text straight to cooked tokens.
+///
+/// As with a raw lexer followed by a cooker, a raw lexer error anywhere in the source
+/// takes precedence over any cooking error.
+pub trait Lexer {
+    fn lex_all(src: &str) -> Result<Vec<Token>, Error>;
+}
+
+/// The cooked lexer made by cooking the output of raw lexer `L` with cooker `C`.
+pub struct Pipeline<L, C> {
+    stages: PhantomData<fn() -> (L, C)>,
+}
+
+impl<L: raw::Lexer, C: Cooker> Lexer for Pipeline<L, C> {
+    fn lex_all(src: &str) -> Result<Vec<Token>, Error> {
+        let raw = L::lex_all(src)?;
+        Ok(C::cook_all(src, &raw)?)
+    }
 }
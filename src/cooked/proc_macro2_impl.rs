@@ -0,0 +1,304 @@
+// Cooks by lexing the source again with `proc_macro2`'s fallback tokenizer,
+// the one used outside of procedural macros.
+//
+// `proc_macro2` skips whitespace and comments, and turns doc comments into attributes,
+// so the raw tokens are used only to fill in the trivia between the tokens it produces.
+
+use {
+    super::{Class::*, *},
+    crate::{
+        line_index::{Encoding, LineCol, LineIndex},
+        raw::{self, SpannedToken},
+    },
+    once_cell::sync::Lazy,
+    proc_macro2::{Delimiter, LineColumn, Span, TokenStream, TokenTree},
+    std::str::FromStr,
+};
+
+/// Whether span columns count bytes or chars, which differs between `proc_macro2` versions,
+/// or `None` if `proc_macro2` fails to lex the probe.
+static COLUMNS: Lazy<Option<Encoding>> = Lazy::new(|| {
+    let probe = TokenStream::from_str("'ü' x").ok()?;
+    match probe.into_iter().nth(1)?.span().start().column {
+        5 => Some(Encoding::Utf8),
+        _ => Some(Encoding::Char),
+    }
+});
+
+/// `proc_macro2` rejected the source, without saying where.
+const REJECTED: CookErrorKind = Implementation("Source rejected by proc_macro2");
+/// `proc_macro2` split the source into tokens other than those cooking would make of the raw tokens.
+const MISMATCHED: CookErrorKind =
+    Implementation("Tokens of proc_macro2 do not match the raw tokens");
+
+pub struct Cooker;
+
+impl super::Cooker for Cooker {
+    /// If `proc_macro2` rejects the source, the error spans all of it,
+    /// as `proc_macro2` does not say where it failed.
+    fn cook_all(src: &str, raw: &[raw::Token]) -> Result<Vec<Token>, CookError> {
+        let rejected = || CookError::new(REJECTED, 0..src.len());
+        let stream = TokenStream::from_str(src).map_err(|_| rejected())?;
+        let mut flat = Flattener {
+            src,
+            index: LineIndex::new(src),
+            columns: COLUMNS.ok_or_else(rejected)?,
+            tokens: vec![],
+            doc: None,
+        };
+        flat.stream(stream)?;
+
+        let raw: Vec<SpannedToken<'_>> = raw::Token::spanned(src, raw.iter().copied()).collect();
+        let mut raw = &*raw;
+        let mut cooked = vec![];
+        for (span, class) in flat.tokens {
+            trivia(&mut raw, span.start, &mut cooked)?;
+            let class = class.ok_or_else(|| CookError::new(MISMATCHED, span.clone()))?;
+            let raw_tokens = raw
+                .iter()
+                .take_while(|token| token.end() <= span.end)
+                .count();
+            if raw_tokens == 0 || raw[raw_tokens - 1].end() != span.end {
+                // `proc_macro2` split or joined raw tokens differently than cooking would.
+                return Err(CookError::new(MISMATCHED, span));
+            }
+            raw = &raw[raw_tokens..];
+            cooked.push(Token {
+                class,
+                length: span.len(),
+                raw_tokens,
+            });
+        }
+        trivia(&mut raw, src.len(), &mut cooked)?;
+        Ok(cooked)
+    }
+}
+
+/// Cooks the raw tokens before `end`, which `proc_macro2` skipped and so must be trivia.
+fn trivia(
+    raw: &mut &[SpannedToken<'_>],
+    end: usize,
+    cooked: &mut Vec<Token>,
+) -> Result<(), CookError> {
+    while let Some(token) = raw.first().filter(|token| token.start < end) {
+        let class = match token.class {
+            raw::whitespace => Trivia_Whitespace,
+            raw::line_comment => Trivia_Comment_Line,
+            raw::block_comment => Trivia_Comment_Block,
+            _ => return Err(CookError::new(MISMATCHED, token.span())),
+        };
+        cooked.push(Token {
+            class,
+            length: token.text.len(),
+            raw_tokens: 1,
+        });
+        *raw = &raw[1..];
+    }
+    Ok(())
+}
+
+/// Flattens a token stream into spans of source, each with a cooked class if it has one.
+struct Flattener<'src> {
+    src: &'src str,
+    index: LineIndex<'src>,
+    columns: Encoding,
+    tokens: Vec<(Range<usize>, Option<Class>)>,
+    /// The span of the doc comment whose desugared attribute is being skipped.
+    doc: Option<Range<usize>>,
+}
+
+impl Flattener<'_> {
+    /// The byte offset of `pos`, or an error spanning all of the source
+    /// if `proc_macro2` gave a position outside of it.
+    fn offset(&self, pos: LineColumn) -> Result<usize, CookError> {
+        let pos = LineCol {
+            line: pos.line.wrapping_sub(1),
+            col: pos.column,
+        };
+        self.index
+            .offset(pos, self.columns)
+            .ok_or_else(|| CookError::new(MISMATCHED, 0..self.src.len()))
+    }
+
+    fn span(&self, span: Span) -> Result<Range<usize>, CookError> {
+        Ok(self.offset(span.start())?..self.offset(span.end())?)
+    }
+
+    fn stream(&mut self, stream: TokenStream) -> Result<(), CookError> {
+        for tree in stream {
+            self.tree(tree)?;
+        }
+        Ok(())
+    }
+
+    fn tree(&mut self, tree: TokenTree) -> Result<(), CookError> {
+        let span = self.span(tree.span())?;
+        if self.doc.as_ref() == Some(&span) {
+            return Ok(());
+        }
+        match tree {
+            // A doc comment is desugared to `#[doc = "..."]` (or `#![doc = "..."]`),
+            // every token of which has the span of the comment.
+            TokenTree::Punct(punct) if punct.as_char() == '#' && span.len() > 1 => {
+                let text = &self.src[span.clone()];
+                let class = if text.starts_with("//!") {
+                    Documentation_Inner_Line
+                } else if text.starts_with("///") {
+                    Documentation_Outer_Line
+                } else if text.starts_with("/*!") {
+                    Documentation_Inner_Block
+                } else {
+                    Documentation_Outer_Block
+                };
+                self.tokens.push((span.clone(), Some(class)));
+                self.doc = Some(span);
+            }
+            TokenTree::Punct(punct) => {
+                self.tokens
+                    .push((span, Class::punctuation(punct.as_char())));
+            }
+            TokenTree::Ident(ident) => {
+                let ident_class = if ident.to_string().starts_with("r#") {
+                    Identifier_Raw
+                } else {
+                    Identifier_Plain
+                };
+                // A lifetime is a `'` punct joined to an identifier.
+                match self.tokens.last_mut() {
+                    Some((quote, class @ None))
+                        if quote.end == span.start && ident_class == Identifier_Plain =>
+                    {
+                        quote.end = span.end;
+                        *class = Some(Lifetime);
+                    }
+                    _ => self.tokens.push((span, Some(ident_class))),
+                }
+            }
+            TokenTree::Literal(_) => {
+                let class = literal(&self.src[span.clone()]);
+                self.tokens.push((span, class));
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => {
+                        (Punctuation_Parenthesis_Open, Punctuation_Parenthesis_Close)
+                    }
+                    Delimiter::Brace => (Punctuation_Brace_Open, Punctuation_Brace_Close),
+                    Delimiter::Bracket => (Punctuation_Bracket_Open, Punctuation_Bracket_Close),
+                    Delimiter::None => return self.stream(group.stream()),
+                };
+                self.tokens.push((span.start..span.start + 1, Some(open)));
+                self.stream(group.stream())?;
+                self.tokens.push((span.end - 1..span.end, Some(close)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The cooked class of a literal's source text,
+/// or `None` for a quoted literal with no closing quote.
+fn literal(text: &str) -> Option<Class> {
+    // The suffix, if any, follows the closing quote and any raw string hashes.
+    let quoted = |quote: char, unsuffixed, suffixed| {
+        let end = text[1..].rfind(quote)? + 2;
+        let end = end + text[end..].len() - text[end..].trim_start_matches('#').len();
+        if end == text.len() {
+            Some(unsuffixed)
+        } else {
+            Some(suffixed)
+        }
+    };
+    if text.starts_with("br") {
+        quoted(
+            '"',
+            Literal_RawByteString_Unsuffixed,
+            Literal_RawByteString_Suffixed,
+        )
+    } else if text.starts_with("b\"") {
+        quoted(
+            '"',
+            Literal_ByteString_Unsuffixed,
+            Literal_ByteString_Suffixed,
+        )
+    } else if text.starts_with("b'") {
+        quoted('\'', Literal_Byte_Unsuffixed, Literal_Byte_Suffixed)
+    } else if text.starts_with('r') {
+        quoted(
+            '"',
+            Literal_RawString_Unsuffixed,
+            Literal_RawString_Suffixed,
+        )
+    } else if text.starts_with('"') {
+        quoted('"', Literal_String_Unsuffixed, Literal_String_Suffixed)
+    } else if text.starts_with('\'') {
+        quoted(
+            '\'',
+            Literal_Character_Unsuffixed,
+            Literal_Character_Suffixed,
+        )
+    } else {
+        Some(number(text))
+    }
+}
+
+/// The cooked class of a numeric literal's source text.
+fn number(text: &str) -> Class {
+    let digits = |s: &str, hex: bool| {
+        s.len()
+            - s.trim_start_matches(|c: char| {
+                c == '_' || c.is_ascii_digit() || (hex && c.is_ascii_hexdigit())
+            })
+            .len()
+    };
+    let (unsuffixed, suffixed, len) = match text.get(..2) {
+        Some("0b") => (
+            Literal_Integer_Binary_Unsuffixed,
+            Literal_Integer_Binary_Suffixed,
+            2 + digits(&text[2..], false),
+        ),
+        Some("0o") => (
+            Literal_Integer_Octal_Unsuffixed,
+            Literal_Integer_Octal_Suffixed,
+            2 + digits(&text[2..], false),
+        ),
+        Some("0x") => (
+            Literal_Integer_Hexadecimal_Unsuffixed,
+            Literal_Integer_Hexadecimal_Suffixed,
+            2 + digits(&text[2..], true),
+        ),
+        _ => {
+            let mut len = digits(text, false);
+            let mut float = false;
+            if text[len..].starts_with('.') {
+                float = true;
+                len += 1 + digits(&text[len + 1..], false);
+            }
+            let exponent = &text[len..];
+            if exponent.starts_with(['e', 'E']) {
+                let sign = exponent[1..].starts_with(['+', '-']) as usize;
+                let exponent_digits = digits(&exponent[1 + sign..], false);
+                if exponent[1 + sign..1 + sign + exponent_digits]
+                    .contains(|c: char| c.is_ascii_digit())
+                {
+                    float = true;
+                    len += 1 + sign + exponent_digits;
+                }
+            }
+            if float {
+                (Literal_Float_Unsuffixed, Literal_Float_Suffixed, len)
+            } else {
+                (
+                    Literal_Integer_Decimal_Unsuffixed,
+                    Literal_Integer_Decimal_Suffixed,
+                    len,
+                )
+            }
+        }
+    };
+    if len == text.len() {
+        unsuffixed
+    } else {
+        suffixed
+    }
+}
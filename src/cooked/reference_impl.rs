@@ -0,0 +1,460 @@
+use {
+    super::{Class::*, *},
+    crate::{
+        coverage,
+        raw::{self, Class::*, SpannedToken},
+    },
+    once_cell::sync::Lazy,
+    regex::Regex,
+    std::{collections::HashMap, sync::Mutex},
+};
+
+/// What a rule produces from the raw tokens it matches.
+enum Cooked {
+    /// `ERROR`, for the reason the spec describes.
+    Error(CookErrorKind),
+    /// One cooked token made from all of the matched raw tokens.
+    Glued(Class),
+    /// One cooked token for each of the matched raw tokens.
+    Each(&'static [Class]),
+}
+
+struct Rule {
+    id: &'static str,
+    raw: &'static [raw::Class],
+    when: When,
+    cooked: Cooked,
+}
+
+impl Rule {
+    fn matches(&self, tokens: &[SpannedToken<'_>]) -> bool {
+        tokens.len() >= self.raw.len()
+            && self
+                .raw
+                .iter()
+                .zip(tokens)
+                .all(|(&class, token)| class == token.class)
+            && self.when.holds(&tokens[..self.raw.len()])
+    }
+}
+
+/// A condition on the text of the raw tokens a rule matches.
+enum When {
+    Always,
+    /// The first token starts with one of `is` but with none of `but_not`.
+    PrefixIs {
+        is: &'static [&'static str],
+        but_not: &'static [&'static str],
+    },
+    /// The first token starts with one of `is` or with none of `or_is_not`.
+    PrefixIsOrIsNot {
+        is: &'static [&'static str],
+        or_is_not: &'static [&'static str],
+    },
+    /// The token at `index` contains a match for `regex`, or does not if `negated`.
+    Matches {
+        index: usize,
+        regex: &'static str,
+        negated: bool,
+    },
+    /// The first token contains a match for `regex` after the first `after`,
+    /// or does not if `negated`.
+    ContainsAfter {
+        after: &'static str,
+        regex: &'static str,
+        negated: bool,
+    },
+}
+
+const fn prefix_is(is: &'static [&'static str], but_not: &'static [&'static str]) -> When {
+    When::PrefixIs { is, but_not }
+}
+
+const fn prefix_is_or_is_not(
+    is: &'static [&'static str],
+    or_is_not: &'static [&'static str],
+) -> When {
+    When::PrefixIsOrIsNot { is, or_is_not }
+}
+
+const fn matches(index: usize, regex: &'static str) -> When {
+    When::Matches {
+        index,
+        regex,
+        negated: false,
+    }
+}
+
+const fn does_not_match(index: usize, regex: &'static str) -> When {
+    When::Matches {
+        index,
+        regex,
+        negated: true,
+    }
+}
+
+const fn contains_after(after: &'static str, regex: &'static str) -> When {
+    When::ContainsAfter {
+        after,
+        regex,
+        negated: false,
+    }
+}
+
+const fn contains_none_after(after: &'static str, regex: &'static str) -> When {
+    When::ContainsAfter {
+        after,
+        regex,
+        negated: true,
+    }
+}
+
+impl When {
+    fn holds(&self, tokens: &[SpannedToken<'_>]) -> bool {
+        let starts_with = |prefixes: &[&str]| {
+            prefixes
+                .iter()
+                .any(|prefix| tokens[0].text.starts_with(prefix))
+        };
+        match *self {
+            When::Always => true,
+            When::PrefixIs { is, but_not } => starts_with(is) && !starts_with(but_not),
+            When::PrefixIsOrIsNot { is, or_is_not } => starts_with(is) || !starts_with(or_is_not),
+            When::Matches {
+                index,
+                regex,
+                negated,
+            } => contains(tokens[index].text, regex) != negated,
+            When::ContainsAfter {
+                after,
+                regex,
+                negated,
+            } => {
+                let text = tokens[0].text;
+                let after = text
+                    .find(after)
+                    .map_or(text.len(), |index| index + after.len());
+                contains(&text[after..], regex) != negated
+            }
+        }
+    }
+
+    /// The condition as `spec/cooked.md` words it after `where`, with code in backticks,
+    /// for a rule matching `raw`.
+    fn describe(&self, raw: &[raw::Class]) -> Option<String> {
+        let code = |texts: &[&str]| {
+            let texts: Vec<String> = texts.iter().map(|text| format!("`{}`", text)).collect();
+            texts.join(" or ")
+        };
+        let token = |index: usize| match raw {
+            [_] => "the token".to_string(),
+            _ => format!("the `{:?}` token", raw[index]),
+        };
+        Some(match *self {
+            When::Always => return None,
+            When::PrefixIs { is, but_not: &[] } => format!("prefix is {}", code(is)),
+            When::PrefixIs { is, but_not } => {
+                format!("prefix is {} but not {}", code(is), code(but_not))
+            }
+            When::PrefixIsOrIsNot { is, or_is_not } => {
+                format!("prefix is {} or is not {}", code(is), code(or_is_not))
+            }
+            When::Matches {
+                index,
+                regex,
+                negated,
+            } => {
+                let anchored = regex.starts_with('^') && regex.ends_with('$');
+                format!(
+                    "{} {} {}{}",
+                    token(index),
+                    if negated { "does not match" } else { "matches" },
+                    if anchored { "" } else { "(unanchored) " },
+                    code(&[regex]),
+                )
+            }
+            When::ContainsAfter {
+                after,
+                regex,
+                negated,
+            } => format!(
+                "{} contains {} for {} after the {}",
+                token(0),
+                if negated { "no match" } else { "a match" },
+                code(&[regex]),
+                code(&[after]),
+            ),
+        })
+    }
+}
+
+/// Whether `text` contains a match for `pattern`.
+fn contains(text: &str, pattern: &'static str) -> bool {
+    static CACHE: Lazy<Mutex<HashMap<&'static str, Regex>>> = Lazy::new(Default::default);
+    let mut cache = CACHE.lock().unwrap();
+    cache
+        .entry(pattern)
+        .or_insert_with(|| Regex::new(pattern).unwrap())
+        .is_match(text)
+}
+
+macro_rules! rules {
+    ($([$first:ident $($rest:ident)*] $(.$tag:ident)? $(if $when:expr)? => $cooked:expr,)*) => {
+        &[$(Rule {
+            id: concat!(
+                "cooked.", stringify!($first), $("-", stringify!($rest),)* $(".", stringify!($tag))?
+            ),
+            raw: &[$first $(, $rest)*],
+            when: rules!(@when $($when)?),
+            cooked: $cooked,
+        },)*]
+    };
+    (@when) => { When::Always };
+    (@when $when:expr) => { $when };
+}
+
+// Reminder: these rules are copied from the specification, in the same order.
+// Do not merge or reorder them; their purpose is to be obviously correct.
+
+static RULES: &[Rule] = rules! {
+    // Unprocessed
+    [whitespace] => Cooked::Glued(Trivia_Whitespace),
+    [identifier] => Cooked::Glued(Identifier_Plain),
+    [raw_identifier] => Cooked::Glued(Identifier_Raw),
+    [lifetime] => Cooked::Glued(Lifetime),
+    [exclamation] => Cooked::Glued(Punctuation_Exclamation),
+    [pound] => Cooked::Glued(Punctuation_Pound),
+    [dollar] => Cooked::Glued(Punctuation_Dollar),
+    [percent] => Cooked::Glued(Punctuation_Percent),
+    [ampersand] => Cooked::Glued(Punctuation_Ampersand),
+    [open_parenthesis] => Cooked::Glued(Punctuation_Parenthesis_Open),
+    [close_parenthesis] => Cooked::Glued(Punctuation_Parenthesis_Close),
+    [star] => Cooked::Glued(Punctuation_Star),
+    [plus] => Cooked::Glued(Punctuation_Plus),
+    [comma] => Cooked::Glued(Punctuation_Comma),
+    [minus] => Cooked::Glued(Punctuation_Minus),
+    [dot] => Cooked::Glued(Punctuation_Dot),
+    [slash] => Cooked::Glued(Punctuation_Slash),
+    [colon] => Cooked::Glued(Punctuation_Colon),
+    [semicolon] => Cooked::Glued(Punctuation_Semicolon),
+    [less] => Cooked::Glued(Punctuation_Less),
+    [equal] => Cooked::Glued(Punctuation_Equal),
+    [greater] => Cooked::Glued(Punctuation_Greater),
+    [question] => Cooked::Glued(Punctuation_Question),
+    [at] => Cooked::Glued(Punctuation_At),
+    [open_bracket] => Cooked::Glued(Punctuation_Bracket_Open),
+    [close_bracket] => Cooked::Glued(Punctuation_Bracket_Close),
+    [circumflex] => Cooked::Glued(Punctuation_Circumflex),
+    [open_brace] => Cooked::Glued(Punctuation_Brace_Open),
+    [bar] => Cooked::Glued(Punctuation_Bar),
+    [close_brace] => Cooked::Glued(Punctuation_Brace_Close),
+    [tilde] => Cooked::Glued(Punctuation_Tilde),
+    [identifier_fragment] => Cooked::Error(InvalidIdentifierStart),
+
+    // Miscelaneous Processed
+    [line_comment] .outer_doc if prefix_is(&["///"], &["////"])
+        => Cooked::Glued(Documentation_Outer_Line),
+    [line_comment] .inner_doc if prefix_is(&["//!"], &[])
+        => Cooked::Glued(Documentation_Inner_Line),
+    [line_comment] .plain if prefix_is_or_is_not(&["////"], &["///", "//!"])
+        => Cooked::Glued(Trivia_Comment_Line),
+    [block_comment] .outer_doc if prefix_is(&["/**"], &["/***", "/**/"])
+        => Cooked::Glued(Documentation_Outer_Block),
+    [block_comment] .inner_doc if prefix_is(&["/*!"], &[])
+        => Cooked::Glued(Documentation_Inner_Block),
+    [block_comment] .plain if prefix_is_or_is_not(&["/***", "/**/"], &["/**", "/*!"])
+        => Cooked::Glued(Trivia_Comment_Block),
+    [lifetime lifetime] => Cooked::Error(MultipleCodepointsInCharacter),
+    [character] => Cooked::Glued(Literal_Character_Unsuffixed),
+    [character identifier] => Cooked::Glued(Literal_Character_Suffixed),
+    [byte] => Cooked::Glued(Literal_Byte_Unsuffixed),
+    [byte identifier] => Cooked::Glued(Literal_Byte_Suffixed),
+    [string] => Cooked::Glued(Literal_String_Unsuffixed),
+    [string identifier] => Cooked::Glued(Literal_String_Suffixed),
+    [byte_string] => Cooked::Glued(Literal_ByteString_Unsuffixed),
+    [byte_string identifier] => Cooked::Glued(Literal_ByteString_Suffixed),
+    [raw_string] => Cooked::Glued(Literal_RawString_Unsuffixed),
+    [raw_string identifier] => Cooked::Glued(Literal_RawString_Suffixed),
+    [raw_byte_string] => Cooked::Glued(Literal_RawByteString_Unsuffixed),
+    [raw_byte_string identifier] => Cooked::Glued(Literal_RawByteString_Suffixed),
+
+    // Not Floating Point
+    [binary_integer dot] => Cooked::Error(BinaryFloat),
+    [binary_integer dot dot] if matches(0, r"^0b_*[01][_01]*$")
+        => Cooked::Each(&[Literal_Integer_Binary_Unsuffixed, Punctuation_Dot, Punctuation_Dot]),
+    [binary_integer dot identifier] if matches(0, r"^0b_*[01][_01]*$")
+        => Cooked::Each(&[Literal_Integer_Binary_Unsuffixed, Punctuation_Dot, Identifier_Plain]),
+    [binary_integer dot raw_identifier] if matches(0, r"^0b_*[01][_01]*$")
+        => Cooked::Each(&[Literal_Integer_Binary_Unsuffixed, Punctuation_Dot, Identifier_Raw]),
+    [octal_integer dot] => Cooked::Error(OctalFloat),
+    [octal_integer dot dot] if matches(0, r"^0o_*[0-7][_0-7]*$")
+        => Cooked::Each(&[Literal_Integer_Octal_Unsuffixed, Punctuation_Dot, Punctuation_Dot]),
+    [octal_integer dot identifier] if matches(0, r"^0o_*[0-7][_0-7]*$")
+        => Cooked::Each(&[Literal_Integer_Octal_Unsuffixed, Punctuation_Dot, Identifier_Plain]),
+    [octal_integer dot raw_identifier] if matches(0, r"^0o_*[0-7][_0-7]*$")
+        => Cooked::Each(&[Literal_Integer_Octal_Unsuffixed, Punctuation_Dot, Identifier_Raw]),
+    [hexadecimal_integer dot] => Cooked::Error(HexadecimalFloat),
+    [hexadecimal_integer dot dot] if matches(0, r"^0x_*[0-9a-fA-F][_0-9a-fA-F]*$")
+        => Cooked::Each(&[Literal_Integer_Hexadecimal_Unsuffixed, Punctuation_Dot, Punctuation_Dot]),
+    [hexadecimal_integer dot identifier] if matches(0, r"^0x_*[0-9a-fA-F][_0-9a-fA-F]*$")
+        => Cooked::Each(&[Literal_Integer_Hexadecimal_Unsuffixed, Punctuation_Dot, Identifier_Plain]),
+    [hexadecimal_integer dot raw_identifier] if matches(0, r"^0x_*[0-9a-fA-F][_0-9a-fA-F]*$")
+        => Cooked::Each(&[Literal_Integer_Hexadecimal_Unsuffixed, Punctuation_Dot, Identifier_Raw]),
+    [decimal_integer dot dot]
+        => Cooked::Each(&[Literal_Integer_Decimal_Unsuffixed, Punctuation_Dot, Punctuation_Dot]),
+    [decimal_integer dot identifier]
+        => Cooked::Each(&[Literal_Integer_Decimal_Unsuffixed, Punctuation_Dot, Identifier_Plain]),
+    [decimal_integer dot raw_identifier]
+        => Cooked::Each(&[Literal_Integer_Decimal_Unsuffixed, Punctuation_Dot, Identifier_Raw]),
+    [binary_float] => Cooked::Error(BinaryFloat),
+    [octal_float] => Cooked::Error(OctalFloat),
+    [decimal_float] .empty_exponent if does_not_match(0, r"[eE][+-]?_*[0-9]")
+        => Cooked::Error(EmptyExponent),
+    [decimal_integer dot decimal_float] .empty_exponent if does_not_match(2, r"[eE][+-]?_*[0-9]")
+        => Cooked::Error(EmptyExponent),
+
+    // Actually Floating Point
+    [decimal_integer dot] => Cooked::Glued(Literal_Float_Unsuffixed),
+    [decimal_integer dot decimal_integer] => Cooked::Glued(Literal_Float_Unsuffixed),
+    [decimal_integer dot decimal_integer identifier] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot decimal_float] if matches(2, r"[eE][+-]?_*[0-9]")
+        => Cooked::Glued(Literal_Float_Unsuffixed),
+    [decimal_float] if matches(0, r"[eE][+-]?_*[0-9]")
+        => Cooked::Glued(Literal_Float_Unsuffixed),
+    [decimal_float identifier] if matches(0, r"[eE][+-]?_*[0-9]")
+        => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot decimal_float identifier] if matches(2, r"[eE][+-]?_*[0-9]")
+        => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot binary_float] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot binary_float identifier] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot binary_float identifier_fragment] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot binary_integer] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot binary_integer identifier] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot binary_integer identifier_fragment] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot octal_float] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot octal_float identifier] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot octal_float identifier_fragment] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot octal_integer] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot octal_integer identifier] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot octal_integer identifier_fragment] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot hexadecimal_integer] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot hexadecimal_integer identifier] => Cooked::Glued(Literal_Float_Suffixed),
+    [decimal_integer dot hexadecimal_integer identifier_fragment]
+        => Cooked::Glued(Literal_Float_Suffixed),
+
+    // Not Numeric Literals
+    [binary_integer] .empty if contains_none_after("b", r"[0-9]")
+        => Cooked::Error(EmptyBinaryInteger),
+    [binary_integer] .invalid_digit if contains_after("b", r"[2-9]")
+        => Cooked::Error(InvalidBinaryDigit),
+    [octal_integer] .empty if contains_none_after("o", r"[0-9]")
+        => Cooked::Error(EmptyOctalInteger),
+    [octal_integer] .invalid_digit if contains_after("o", r"[8-9]")
+        => Cooked::Error(InvalidOctalDigit),
+    [hexadecimal_integer] .empty if contains_none_after("x", r"[0-9a-fA-F]")
+        => Cooked::Error(EmptyHexadecimalInteger),
+
+    // Actually Numeric Literals
+    [binary_integer] if matches(0, r"^0b_*[01][_01]*$")
+        => Cooked::Glued(Literal_Integer_Binary_Unsuffixed),
+    [binary_integer identifier] if matches(0, r"^0b_*[01][_01]*$")
+        => Cooked::Glued(Literal_Integer_Binary_Suffixed),
+    [octal_integer] if matches(0, r"^0o_*[0-7][_0-7]*$")
+        => Cooked::Glued(Literal_Integer_Octal_Unsuffixed),
+    [octal_integer identifier] if matches(0, r"^0o_*[0-7][_0-7]*$")
+        => Cooked::Glued(Literal_Integer_Octal_Suffixed),
+    [hexadecimal_integer] if matches(0, r"^0x_*[0-9a-fA-F][_0-9a-fA-F]*$")
+        => Cooked::Glued(Literal_Integer_Hexadecimal_Unsuffixed),
+    [hexadecimal_integer identifier] if matches(0, r"^0x_*[0-9a-fA-F][_0-9a-fA-F]*$")
+        => Cooked::Glued(Literal_Integer_Hexadecimal_Suffixed),
+    [decimal_integer] => Cooked::Glued(Literal_Integer_Decimal_Unsuffixed),
+    [decimal_integer identifier] => Cooked::Glued(Literal_Integer_Decimal_Suffixed),
+};
+
+/// What a rule cooks its raw tokens to:
+/// the cooked class of each of them, or of all of them, or why they are an error.
+pub type Cooking = Result<&'static [Class], CookErrorKind>;
+
+/// Each rule in order, as its ID, the raw classes it matches, any condition on their text
+/// (worded as in `spec/cooked.md`, with code in backticks) and what it cooks them to.
+pub fn rules(
+) -> impl Iterator<Item = (&'static str, &'static [raw::Class], Option<String>, Cooking)> {
+    RULES.iter().map(|rule| {
+        let cooked = match &rule.cooked {
+            Cooked::Error(kind) => Err(*kind),
+            Cooked::Glued(class) => Ok(std::slice::from_ref(class)),
+            Cooked::Each(classes) => Ok(*classes),
+        };
+        (rule.id, rule.raw, rule.when.describe(rule.raw), cooked)
+    })
+}
+
+/// Cooks `raw` as [`Cooker`] does, giving the ID of the rule which produced each cooked token.
+///
+/// Stops at the first error, given with the ID of the rule which found it if one matched.
+pub fn explain(
+    src: &str,
+    raw: &[raw::Token],
+) -> Vec<(Option<&'static str>, Result<Token, CookError>)> {
+    let raw: Vec<SpannedToken<'_>> = raw::Token::spanned(src, raw.iter().copied()).collect();
+    let mut cooked = vec![];
+    let mut raw = &*raw;
+    while !raw.is_empty() {
+        let mut matching = RULES.iter().filter(|rule| rule.matches(raw));
+        let span = |tokens: &[SpannedToken<'_>]| tokens[0].start..tokens[tokens.len() - 1].end();
+        let rule = match matching.next() {
+            Some(rule) => rule,
+            None => {
+                cooked.push((None, Err(CookError::new(NoMatchingCase, span(&raw[..1])))));
+                break;
+            }
+        };
+        let rule = matching.fold(rule, |longest, rule| {
+            debug_assert_ne!(longest.raw.len(), rule.raw.len(), "ambiguous cooking rules");
+            if rule.raw.len() > longest.raw.len() {
+                rule
+            } else {
+                longest
+            }
+        });
+        coverage::hit(rule.id);
+        let (matched, rest) = raw.split_at(rule.raw.len());
+        match rule.cooked {
+            Cooked::Error(kind) => {
+                cooked.push((Some(rule.id), Err(CookError::new(kind, span(matched)))));
+                break;
+            }
+            Cooked::Glued(class) => cooked.push((
+                Some(rule.id),
+                Ok(Token {
+                    class,
+                    length: matched.iter().map(|token| token.text.len()).sum(),
+                    raw_tokens: matched.len(),
+                }),
+            )),
+            Cooked::Each(classes) => {
+                cooked.extend(classes.iter().zip(matched).map(|(&class, token)| {
+                    let token = Token {
+                        class,
+                        length: token.text.len(),
+                        raw_tokens: 1,
+                    };
+                    (Some(rule.id), Ok(token))
+                }))
+            }
+        }
+        raw = rest;
+    }
+    cooked
+}
+
+pub struct Cooker;
+
+impl super::Cooker for Cooker {
+    fn cook_all(src: &str, raw: &[raw::Token]) -> Result<Vec<Token>, CookError> {
+        explain(src, raw)
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect()
+    }
+}
@@ -0,0 +1,581 @@
+// A single pass from source text to cooked tokens, in the style of `rustc_lexer`:
+// each token is recognized by its first char, an identifier directly after a literal
+// is its suffix, and floats are recognized by looking ahead past the `.`.
+//
+// This must accept exactly the language of the reference raw lexer and cooker,
+// so the lookahead here decides which raw token would come next where the cooker would.
+
+use {
+    super::{Class::*, *},
+    crate::raw::{self, LexError, LexErrorKind::*},
+    unicode_xid::UnicodeXID,
+};
+
+pub struct Lexer;
+
+impl super::Lexer for Lexer {
+    fn lex_all(src: &str) -> Result<Vec<Token>, Error> {
+        let mut tokens = vec![];
+        let mut cook_error = None;
+        let mut offset = 0;
+        while offset < src.len() {
+            let mut cursor = Cursor::new(&src[offset..]);
+            let cooked =
+                cook(&mut cursor).map_err(|err| LexError::new(err.kind, offset + err.offset))?;
+            let span = offset..offset + cursor.len;
+            match cooked {
+                Ok(class) => tokens.push(Token {
+                    class,
+                    length: cursor.len,
+                    raw_tokens: cursor.raw_tokens,
+                }),
+                // Keep going; a raw lexer error later in the source takes precedence.
+                Err(kind) => {
+                    cook_error.get_or_insert(CookError::new(kind, span.clone()));
+                }
+            }
+            offset = span.end;
+        }
+        match cook_error {
+            Some(err) => Err(err.into()),
+            None => Ok(tokens),
+        }
+    }
+}
+
+/// The cooked token being recognized at the front of `src`.
+struct Cursor<'src> {
+    src: &'src str,
+    /// Bytes of `src` in the token so far.
+    len: usize,
+    /// Raw tokens in the token so far.
+    raw_tokens: usize,
+}
+
+impl<'src> Cursor<'src> {
+    fn new(src: &'src str) -> Self {
+        Cursor {
+            src,
+            len: 0,
+            raw_tokens: 0,
+        }
+    }
+
+    fn rest(&self) -> &'src str {
+        &self.src[self.len..]
+    }
+
+    /// Adds the next raw token, `len` bytes long, to the token.
+    fn bump(&mut self, len: usize) {
+        self.len += len;
+        self.raw_tokens += 1;
+    }
+
+    /// Adds the next raw token to the token if it is an `identifier` suffix.
+    fn suffix(&mut self) -> bool {
+        match identifier(self.rest()) {
+            Some(len) => {
+                self.bump(len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The suffixed class if the literal has a suffix, else the unsuffixed class.
+    fn literal(&mut self, suffixed: Class, unsuffixed: Class) -> Class {
+        if self.suffix() {
+            suffixed
+        } else {
+            unsuffixed
+        }
+    }
+}
+
+/// Recognizes the token at the front of the cursor, which must not be empty.
+fn cook(cursor: &mut Cursor<'_>) -> Result<Result<Class, CookErrorKind>, LexError> {
+    let s = cursor.rest();
+    let mut chars = s.chars();
+    let (first, second, third) = (chars.next().unwrap(), chars.next(), chars.next());
+    let class = match first {
+        '/' if second == Some('/') => {
+            let len = s.find('\n').unwrap_or(s.len());
+            cursor.bump(len);
+            let text = &s[..len];
+            if text.starts_with("///") && !text.starts_with("////") {
+                Documentation_Outer_Line
+            } else if text.starts_with("//!") {
+                Documentation_Inner_Line
+            } else {
+                Trivia_Comment_Line
+            }
+        }
+        '/' if second == Some('*') => {
+            let len = block_comment(s)?;
+            cursor.bump(len);
+            let text = &s[..len];
+            if text.starts_with("/**") && !text.starts_with("/***") && !text.starts_with("/**/") {
+                Documentation_Outer_Block
+            } else if text.starts_with("/*!") {
+                Documentation_Inner_Block
+            } else {
+                Trivia_Comment_Block
+            }
+        }
+        c if is_whitespace(c) => {
+            cursor.bump(s.find(|c| !is_whitespace(c)).unwrap_or(s.len()));
+            Trivia_Whitespace
+        }
+        'r' if second == Some('#') && matches!(third, Some(c) if is_identifier_start(c)) => {
+            cursor.bump(2 + identifier_continue(&s[2..]));
+            Identifier_Raw
+        }
+        'r' if second == Some('#') || second == Some('"') => {
+            cursor.bump(raw_string(s, 1, false)?);
+            cursor.literal(Literal_RawString_Suffixed, Literal_RawString_Unsuffixed)
+        }
+        'b' if second == Some('r') && (third == Some('#') || third == Some('"')) => {
+            cursor.bump(raw_string(s, 2, true)?);
+            cursor.literal(
+                Literal_RawByteString_Suffixed,
+                Literal_RawByteString_Unsuffixed,
+            )
+        }
+        'b' if second == Some('"') || second == Some('\'') => {
+            let literal = if second == Some('"') {
+                byte_string(s).map(|len| {
+                    (
+                        len,
+                        Literal_ByteString_Suffixed,
+                        Literal_ByteString_Unsuffixed,
+                    )
+                })
+            } else {
+                byte(s).map(|len| (len, Literal_Byte_Suffixed, Literal_Byte_Unsuffixed))
+            };
+            match literal {
+                Some((len, suffixed, unsuffixed)) => {
+                    cursor.bump(len);
+                    cursor.literal(suffixed, unsuffixed)
+                }
+                // Just the identifier `b`; the quote starts the next token.
+                None => {
+                    cursor.bump(1);
+                    Identifier_Plain
+                }
+            }
+        }
+        '\'' => {
+            if let Some(len) = character(s) {
+                cursor.bump(len);
+                cursor.literal(Literal_Character_Suffixed, Literal_Character_Unsuffixed)
+            } else if let Some(len) = lifetime(s) {
+                cursor.bump(len);
+                if character(cursor.rest()).is_none() {
+                    if let Some(len) = lifetime(cursor.rest()) {
+                        cursor.bump(len);
+                        return Ok(Err(MultipleCodepointsInCharacter));
+                    }
+                }
+                Lifetime
+            } else {
+                Err(LexError::new(UnknownStartOfToken, 0))?
+            }
+        }
+        '"' => match string(s) {
+            Some(len) => {
+                cursor.bump(len);
+                cursor.literal(Literal_String_Suffixed, Literal_String_Unsuffixed)
+            }
+            None => Err(LexError::new(UnknownStartOfToken, 0))?,
+        },
+        '0'..='9' => return Ok(number(cursor)),
+        c if is_identifier_start(c) => {
+            cursor.bump(identifier_continue(s));
+            Identifier_Plain
+        }
+        c if c.is_xid_continue() => {
+            cursor.bump(identifier_continue(s));
+            return Ok(Err(InvalidIdentifierStart));
+        }
+        _ => match Class::punctuation(first) {
+            Some(class) => {
+                cursor.bump(1);
+                class
+            }
+            None => Err(LexError::new(UnknownStartOfToken, 0))?,
+        },
+    };
+    Ok(Ok(class))
+}
+
+/// Recognizes a numeric literal, including a float glued from `1.5` or `1.5e10`.
+fn number(cursor: &mut Cursor<'_>) -> Result<Class, CookErrorKind> {
+    let (class, len) = number_prefix(cursor.rest());
+    let text = &cursor.rest()[..len];
+    cursor.bump(len);
+    let (is_digit, unsuffixed, suffixed, float): (fn(char) -> bool, _, _, _) = match class {
+        raw::binary_float => return Err(BinaryFloat),
+        raw::octal_float => return Err(OctalFloat),
+        raw::decimal_float if has_exponent_digits(text) => {
+            return Ok(cursor.literal(Literal_Float_Suffixed, Literal_Float_Unsuffixed))
+        }
+        raw::decimal_float => return Err(EmptyExponent),
+        raw::decimal_integer => return decimal(cursor),
+        raw::binary_integer => (
+            |c| matches!(c, '0'..='1'),
+            Literal_Integer_Binary_Unsuffixed,
+            Literal_Integer_Binary_Suffixed,
+            BinaryFloat,
+        ),
+        raw::octal_integer => (
+            |c| matches!(c, '0'..='7'),
+            Literal_Integer_Octal_Unsuffixed,
+            Literal_Integer_Octal_Suffixed,
+            OctalFloat,
+        ),
+        raw::hexadecimal_integer => (
+            |c| c.is_ascii_hexdigit(),
+            Literal_Integer_Hexadecimal_Unsuffixed,
+            Literal_Integer_Hexadecimal_Suffixed,
+            HexadecimalFloat,
+        ),
+        _ => unreachable!(),
+    };
+    let digits = &text[2..];
+    let valid = digits.contains(is_digit) && digits.chars().all(|c| c == '_' || is_digit(c));
+    if cursor.rest().starts_with('.') {
+        // `0b1..` and `0b1.foo` are an integer followed by punctuation;
+        // anything else after the `.` makes a float in the wrong base.
+        let after = &cursor.rest()[1..];
+        if valid
+            && (after.starts_with('.')
+                || identifier(after).is_some()
+                || raw_identifier(after).is_some())
+        {
+            return Ok(unsuffixed);
+        }
+        cursor.bump(1);
+        return Err(float);
+    }
+    if valid {
+        Ok(cursor.literal(suffixed, unsuffixed))
+    } else {
+        Err(match class {
+            raw::binary_integer if digits.contains(|c: char| c.is_ascii_digit()) => {
+                InvalidBinaryDigit
+            }
+            raw::binary_integer => EmptyBinaryInteger,
+            raw::octal_integer if digits.contains(|c: char| c.is_ascii_digit()) => {
+                InvalidOctalDigit
+            }
+            raw::octal_integer => EmptyOctalInteger,
+            _ => EmptyHexadecimalInteger,
+        })
+    }
+}
+
+/// Recognizes a decimal integer, or a float if it is followed by a `.` starting a float.
+fn decimal(cursor: &mut Cursor<'_>) -> Result<Class, CookErrorKind> {
+    if !cursor.rest().starts_with('.') {
+        return Ok(cursor.literal(
+            Literal_Integer_Decimal_Suffixed,
+            Literal_Integer_Decimal_Unsuffixed,
+        ));
+    }
+    let after = &cursor.rest()[1..];
+    if after.starts_with('.') || identifier(after).is_some() || raw_identifier(after).is_some() {
+        // Field access, method call, or range.
+        return Ok(Literal_Integer_Decimal_Unsuffixed);
+    }
+    cursor.bump(1);
+    if !after.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(Literal_Float_Unsuffixed);
+    }
+    let (class, len) = number_prefix(after);
+    cursor.bump(len);
+    Ok(match class {
+        raw::decimal_integer => cursor.literal(Literal_Float_Suffixed, Literal_Float_Unsuffixed),
+        raw::decimal_float if has_exponent_digits(&after[..len]) => {
+            cursor.literal(Literal_Float_Suffixed, Literal_Float_Unsuffixed)
+        }
+        raw::decimal_float => return Err(EmptyExponent),
+        // `1.0b1` and friends: everything after the `0` is the suffix,
+        // including an `identifier_fragment` such as in `1.0b·`.
+        _ => {
+            if !cursor.suffix() {
+                if let Some(len) = identifier_fragment(cursor.rest()) {
+                    cursor.bump(len);
+                }
+            }
+            Literal_Float_Suffixed
+        }
+    })
+}
+
+/// The raw numeric token at the front of `s`, which starts with a digit.
+fn number_prefix(s: &str) -> (raw::Class, usize) {
+    let bytes = s.as_bytes();
+    let (integer, float, start) = match bytes.get(..2) {
+        Some(b"0b") => (raw::binary_integer, Some(raw::binary_float), 2),
+        Some(b"0o") => (raw::octal_integer, Some(raw::octal_float), 2),
+        Some(b"0x") => (raw::hexadecimal_integer, None, 2),
+        _ => (raw::decimal_integer, Some(raw::decimal_float), 1),
+    };
+    let is_digit = match integer {
+        raw::hexadecimal_integer => u8::is_ascii_hexdigit,
+        _ => u8::is_ascii_digit,
+    };
+    let mut len = start + count(&bytes[start..], |b| b == b'_' || is_digit(&b));
+    match float {
+        Some(float) if matches!(bytes.get(len), Some(b'e') | Some(b'E')) => {
+            len += 1;
+            if matches!(bytes.get(len), Some(b'+') | Some(b'-')) {
+                len += 1;
+            }
+            len += count(&bytes[len..], |b| b == b'_' || b.is_ascii_digit());
+            (float, len)
+        }
+        _ => (integer, len),
+    }
+}
+
+/// Whether a `decimal_float` matches `[eE][+-]?_*[0-9]`.
+fn has_exponent_digits(text: &str) -> bool {
+    let exponent = &text[text.find(['e', 'E']).unwrap() + 1..];
+    let exponent = exponent.trim_start_matches(['+', '-']);
+    exponent
+        .trim_start_matches('_')
+        .starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn count(bytes: &[u8], pred: impl Fn(u8) -> bool) -> usize {
+    bytes.iter().take_while(|&&b| pred(b)).count()
+}
+
+/// `\p{Pattern_White_Space}`
+fn is_whitespace(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | '\n'
+            | '\u{B}'
+            | '\u{C}'
+            | '\r'
+            | ' '
+            | '\u{85}'
+            | '\u{200E}'
+            | '\u{200F}'
+            | '\u{2028}'
+            | '\u{2029}'
+    )
+}
+
+/// `[_\p{XID_Start}]`
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || c.is_xid_start()
+}
+
+/// The length of the `\p{XID_Continue}*` at the front of `s`.
+fn identifier_continue(s: &str) -> usize {
+    s.find(|c: char| !c.is_xid_continue()).unwrap_or(s.len())
+}
+
+/// The length of an `identifier` raw token at the front of `s`,
+/// unless a prefixed literal or raw identifier starts there instead.
+fn identifier(s: &str) -> Option<usize> {
+    if !s.starts_with(is_identifier_start) {
+        return None;
+    }
+    let bytes = s.as_bytes();
+    let prefixed = match bytes {
+        [b'r', b'#', ..] | [b'r', b'"', ..] => true,
+        [b'b', b'r', b'#', ..] | [b'b', b'r', b'"', ..] => true,
+        [b'b', b'"', ..] => byte_string(s).is_some(),
+        [b'b', b'\'', ..] => byte(s).is_some(),
+        _ => false,
+    };
+    if prefixed {
+        None
+    } else {
+        Some(identifier_continue(s))
+    }
+}
+
+fn raw_identifier(s: &str) -> Option<usize> {
+    if s.starts_with("r#") && s[2..].starts_with(is_identifier_start) {
+        Some(2 + identifier_continue(&s[2..]))
+    } else {
+        None
+    }
+}
+
+fn identifier_fragment(s: &str) -> Option<usize> {
+    let c = s.chars().next()?;
+    if c.is_xid_continue() && !is_identifier_start(c) && !c.is_ascii_digit() {
+        Some(identifier_continue(s))
+    } else {
+        None
+    }
+}
+
+fn lifetime(s: &str) -> Option<usize> {
+    if s.starts_with('\'') && s[1..].starts_with(is_identifier_start) {
+        Some(1 + identifier_continue(&s[1..]))
+    } else {
+        None
+    }
+}
+
+/// The length of the escape at the front of `s`, which starts with `\`.
+///
+/// Unicode escapes and ASCII `\x` escapes are allowed only outside of byte literals,
+/// which allow any `\x` escape instead.
+fn escape(s: &str, bytes: bool) -> Option<usize> {
+    let s = s.as_bytes();
+    match s.get(1)? {
+        b'\'' | b'"' | b'n' | b'r' | b't' | b'\\' | b'0' => Some(2),
+        b'x' => {
+            let high = *s.get(2)?;
+            let low = *s.get(3)?;
+            let high_ok = if bytes {
+                high.is_ascii_hexdigit()
+            } else {
+                matches!(high, b'0'..=b'7')
+            };
+            if high_ok && low.is_ascii_hexdigit() {
+                Some(4)
+            } else {
+                None
+            }
+        }
+        // `\u\{(?:10|[0-9])[0-9a-fA-F]{0,4}\}`
+        b'u' if !bytes && s.get(2) == Some(&b'{') => {
+            let digits = count(&s[3..], |b| b.is_ascii_hexdigit());
+            let max = if s[3..].starts_with(b"10") { 6 } else { 5 };
+            let ok = matches!(s.get(3), Some(b'0'..=b'9')) && digits <= max;
+            if ok && s.get(3 + digits) == Some(&b'}') {
+                Some(4 + digits)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The length of a quoted char or byte at the front of `s`, opened by `prefix`.
+fn quoted_char(s: &str, prefix: &str, bytes: bool) -> Option<usize> {
+    if !s.starts_with(prefix) {
+        return None;
+    }
+    let mut len = prefix.len();
+    let c = s[len..].chars().next()?;
+    match c {
+        '\\' => len += escape(&s[len..], bytes)?,
+        '\t' | '\n' | '\r' | '\'' => return None,
+        c if bytes && !c.is_ascii() => return None,
+        c => len += c.len_utf8(),
+    }
+    if s[len..].starts_with('\'') {
+        Some(len + 1)
+    } else {
+        None
+    }
+}
+
+fn character(s: &str) -> Option<usize> {
+    quoted_char(s, "'", false)
+}
+
+fn byte(s: &str) -> Option<usize> {
+    quoted_char(s, "b'", true)
+}
+
+/// The length of a quoted string at the front of `s`, opened by `prefix`.
+fn quoted_string(s: &str, prefix: &str, bytes: bool) -> Option<usize> {
+    if !s.starts_with(prefix) {
+        return None;
+    }
+    let mut len = prefix.len();
+    loop {
+        let c = s[len..].chars().next()?;
+        match c {
+            '"' => return Some(len + 1),
+            '\r' if s[len + 1..].starts_with('\n') => len += 2,
+            '\r' => return None,
+            '\\' if s[len + 1..].starts_with('\n') => {
+                len += 2;
+                len += s[len..]
+                    .find(|c| !is_whitespace(c))
+                    .unwrap_or_else(|| s.len() - len);
+            }
+            '\\' => len += escape(&s[len..], bytes)?,
+            c if bytes && !c.is_ascii() => return None,
+            c => len += c.len_utf8(),
+        }
+    }
+}
+
+fn string(s: &str) -> Option<usize> {
+    quoted_string(s, "\"", false)
+}
+
+fn byte_string(s: &str) -> Option<usize> {
+    quoted_string(s, "b\"", true)
+}
+
+fn block_comment(s: &str) -> Result<usize, LexError> {
+    let bytes = s.as_bytes();
+    let mut depth = 1;
+    let mut len = 2;
+    while depth > 0 {
+        match bytes.get(len..len + 2) {
+            Some(b"/*") => {
+                depth += 1;
+                len += 2;
+            }
+            Some(b"*/") => {
+                depth -= 1;
+                len += 2;
+            }
+            _ if len < bytes.len() => len += 1,
+            _ => return Err(LexError::new(UnterminatedBlockComment, s.len())),
+        }
+    }
+    Ok(len)
+}
+
+/// The length of a raw string at the front of `s`, after `prefix` bytes of `r` or `br`.
+fn raw_string(s: &str, prefix: usize, bytes: bool) -> Result<usize, LexError> {
+    let (invalid_fence, unterminated, bare_cr) = if bytes {
+        (
+            InvalidRawByteStringFence,
+            UnterminatedRawByteString,
+            BareCrInRawByteString,
+        )
+    } else {
+        (
+            InvalidRawStringFence,
+            UnterminatedRawString,
+            BareCrInRawString,
+        )
+    };
+    let hashes = count(&s.as_bytes()[prefix..], |b| b == b'#');
+    let mut len = prefix + hashes;
+    match s[len..].chars().next() {
+        Some('"') => len += 1,
+        Some(_) => return Err(LexError::new(invalid_fence, len)),
+        None => return Err(LexError::new(unterminated, len)),
+    }
+    let fence = &s[prefix..prefix + hashes];
+    for (ix, c) in s[len..].char_indices() {
+        let ix = len + ix;
+        match c {
+            '"' if s[ix + 1..].starts_with(fence) => return Ok(ix + 1 + hashes),
+            '\r' if !s[ix + 1..].starts_with('\n') => return Err(LexError::new(bare_cr, ix)),
+            c if bytes && !c.is_ascii() => return Err(LexError::new(NonAsciiInRawByteString, ix)),
+            _ => {}
+        }
+    }
+    Err(LexError::new(unterminated, s.len()))
+}
@@ -0,0 +1,48 @@
+//! Which rules of the specification the reference implementations have applied, by stable ID.
+//!
+//! The IDs are
+//!
+//! - `lex.…` for how the raw lexer picks a class when no regular expression matches,
+//! - `tie.…` for each tie rule of the raw lexer, named after the class it prefers,
+//! - `block_comment.…`, `raw_string.…` and `raw_byte_string.…`
+//!   for each outcome of the recognizers of the nonregular raw classes, and
+//! - `cooked.…` for each rule of the lexer cooker, named after the raw classes it matches.
+//!
+//! Tie rules and cooking rules carry the same ID as an `id` in `spec/raw.md` and `spec/cooked.md`.
+//!
+//! Applied rules are only recorded with the `coverage` feature.
+
+use crate::{cooked, raw};
+#[cfg(feature = "coverage")]
+use {
+    once_cell::sync::Lazy,
+    std::{collections::BTreeSet, sync::Mutex},
+};
+
+#[cfg(feature = "coverage")]
+static HITS: Lazy<Mutex<BTreeSet<&'static str>>> = Lazy::new(Default::default);
+
+/// The ID of every rule, in the order of the specification.
+pub fn rules() -> Vec<&'static str> {
+    let mut rules = vec!["lex.unknown_start", "tie.single"];
+    rules.extend(raw::reference_impl::TIES.iter().map(|&(id, ..)| id));
+    rules.extend(raw::reference_impl::BRANCHES.iter().map(|&(id, ..)| id));
+    rules.extend(cooked::reference_impl::rules().map(|(id, ..)| id));
+    rules
+}
+
+/// Records that the rule `id` was applied.
+#[cfg(feature = "coverage")]
+pub(crate) fn hit(id: &'static str) {
+    HITS.lock().unwrap().insert(id);
+}
+
+#[cfg(not(feature = "coverage"))]
+#[inline(always)]
+pub(crate) fn hit(_id: &'static str) {}
+
+/// The ID of every rule applied so far by this process.
+#[cfg(feature = "coverage")]
+pub fn hits() -> BTreeSet<&'static str> {
+    HITS.lock().unwrap().clone()
+}
@@ -0,0 +1,134 @@
+//! Why the reference implementations lex a source as they do,
+//! by the rules of the specification which produce each token.
+//!
+//! Rules are named by the IDs of [`coverage`](crate::coverage).
+
+use {
+    crate::{
+        cooked,
+        raw::{self, reference_impl, Lexer as _},
+    },
+    std::fmt,
+};
+
+/// How the reference raw lexer lexed one raw token.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RawExplanation<'src> {
+    /// The token, or the source skipped and why where lexing failed.
+    pub token: raw::SpannedToken<'src, Result<raw::Class, raw::LexError>>,
+    /// The classes whose regular expressions match a prefix of the source from the token on,
+    /// and the rule which picked the class from them or failed,
+    /// or `None` where a literal with no closing quote was skipped without lexing it.
+    pub decision: Option<reference_impl::Decision>,
+    /// The recognizer of the nonregular class picked, which found the end of the token.
+    pub recognizer: Option<&'static str>,
+}
+
+/// How the reference cooker cooked one token, or failed to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CookedExplanation<'src> {
+    /// The ID of the rule of `spec/cooked.md` which matched, if one did.
+    pub rule: Option<&'static str>,
+    /// The token the rule produced, or why cooking failed.
+    pub token: Result<cooked::SpannedToken<'src>, cooked::CookError>,
+}
+
+/// The recognizer of `class`, as named in `spec/raw.md`, if it is nonregular.
+pub fn recognizer(class: raw::Class) -> Option<&'static str> {
+    match class {
+        raw::block_comment => Some("parse_block_comment"),
+        raw::raw_string => Some("parse_raw_string"),
+        raw::raw_byte_string => Some("parse_raw_byte_string"),
+        _ => None,
+    }
+}
+
+/// Explains each raw token of `src`, recovering from errors as `lex_all_recovering` does.
+pub fn raw(src: &str) -> Vec<RawExplanation<'_>> {
+    let mut explanations = vec![];
+    let mut offset = 0;
+    while offset < src.len() {
+        let mut decision = None;
+        let token = raw::RecoveredToken::lex(src, offset, |rest| {
+            let (lexed, decided) = reference_impl::Lexer::lex_deciding(rest);
+            decision = Some(decided);
+            lexed
+        });
+        let token = raw::SpannedToken::new(src, offset, token);
+        offset += token.text.len();
+        explanations.push(RawExplanation {
+            token,
+            recognizer: decision
+                .as_ref()
+                .and_then(|decision| decision.class)
+                .and_then(recognizer),
+            decision,
+        });
+    }
+    explanations
+}
+
+/// Explains each cooked token of `src`, up to and including any cooking error.
+///
+/// Fails if the raw lexer does, as cooking needs all of the raw tokens.
+pub fn cooked(src: &str) -> Result<Vec<CookedExplanation<'_>>, raw::LexError> {
+    let raw = reference_impl::Lexer::lex_all(src)?;
+    let explained = cooked::reference_impl::explain(src, &raw);
+    let tokens: Vec<cooked::Token> = explained
+        .iter()
+        .filter_map(|(_, token)| token.as_ref().ok().copied())
+        .collect();
+    let mut spanned = cooked::Token::spanned(src, tokens);
+    Ok(explained
+        .into_iter()
+        .map(|(rule, token)| CookedExplanation {
+            rule,
+            token: token.map(|_| spanned.next().unwrap()),
+        })
+        .collect())
+}
+
+impl fmt::Display for RawExplanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let token = &self.token;
+        match token.class {
+            Ok(class) => write!(f, "{:?}", class)?,
+            Err(_) => write!(f, "error")?,
+        }
+        write!(f, " {:?} at {:?}", token.text, token.span())?;
+        if let Some(decision) = &self.decision {
+            let matches: Vec<String> = decision
+                .matches
+                .iter()
+                .map(|c| format!("{:?}", c))
+                .collect();
+            match &*matches {
+                [] => write!(f, ", matched by no class")?,
+                matches => write!(f, ", matched by {}", matches.join(" "))?,
+            }
+            if let Some(rule) = decision.rule {
+                write!(f, ", by {}", rule)?;
+            }
+        }
+        if let Some(recognizer) = self.recognizer {
+            write!(f, ", recognized by {}", recognizer)?;
+        }
+        if let Err(err) = token.class {
+            write!(f, ": {}", err)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CookedExplanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.token {
+            Ok(token) => write!(f, "{} {:?} at {:?}", token.class, token.text, token.span())?,
+            Err(err) => write!(f, "ERROR {}", err)?,
+        }
+        match self.rule {
+            Some(rule) => write!(f, ", by {}", rule),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,2 +1,6 @@
 pub mod cooked;
+pub mod coverage;
+pub mod explain;
+pub mod line_index;
+pub mod minimize;
 pub mod raw;
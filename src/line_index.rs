@@ -0,0 +1,102 @@
+//! Conversion between byte offsets and (line, column) positions.
+//!
+//! Lines are terminated by `\n`, as for `line_comment` in the raw lexer.
+//! A bare `\r` does not end a line; in a `\r\n` pair, the `\r` is the last column of its line.
+
+/// Unit in which columns are counted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Encoding {
+    /// UTF-8 bytes.
+    Utf8,
+    /// UTF-16 code units, as used by the Language Server Protocol.
+    Utf16,
+    /// Unicode scalar values.
+    Char,
+}
+
+impl Encoding {
+    fn len(self, c: char) -> usize {
+        match self {
+            Encoding::Utf8 => c.len_utf8(),
+            Encoding::Utf16 => c.len_utf16(),
+            Encoding::Char => 1,
+        }
+    }
+}
+
+/// A zero-based line and column.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineIndex<'src> {
+    src: &'src str,
+    /// Byte offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'src> LineIndex<'src> {
+    pub fn new(src: &'src str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(src.match_indices('\n').map(|(ix, _)| ix + 1));
+        LineIndex { src, line_starts }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The text of a line, including its terminator.
+    pub fn line(&self, line: usize) -> Option<&'src str> {
+        let start = *self.line_starts.get(line)?;
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.src.len());
+        Some(&self.src[start..end])
+    }
+
+    /// The position of a byte offset, or `None` if it is not a char boundary in the source.
+    pub fn line_col(&self, offset: usize, encoding: Encoding) -> Option<LineCol> {
+        if !self.src.is_char_boundary(offset) {
+            return None;
+        }
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let col = self.src[self.line_starts[line]..offset]
+            .chars()
+            .map(|c| encoding.len(c))
+            .sum();
+        Some(LineCol { line, col })
+    }
+
+    /// The byte offset of a position, or `None` if it is past the end of its line
+    /// or in the middle of a char.
+    ///
+    /// The position just past the last char of a line (before its `\n`) is valid.
+    pub fn offset(&self, pos: LineCol, encoding: Encoding) -> Option<usize> {
+        let line = self.line(pos.line)?;
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let mut col = 0;
+        for (ix, c) in line.char_indices() {
+            if col == pos.col {
+                return Some(self.line_starts[pos.line] + ix);
+            }
+            col += encoding.len(c);
+            if col > pos.col {
+                return None;
+            }
+        }
+        if col == pos.col {
+            Some(self.line_starts[pos.line] + line.len())
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Shrinking inputs on which two lexers disagree, by delta debugging.
+//!
+//! The result is 1-minimal: removing any single character from it
+//! makes the lexers agree.
+
+use crate::{cooked, raw};
+
+/// Shrinks `input` to a string on which `fails` still holds,
+/// such that removing any one character from it makes `fails` not hold.
+///
+/// `fails` must hold on `input`.
+pub fn minimize(input: &str, mut fails: impl FnMut(&str) -> bool) -> String {
+    assert!(fails(input), "input to minimize does not fail");
+    let mut chars: Vec<char> = input.chars().collect();
+    let mut granularity = 2;
+    while !chars.is_empty() {
+        granularity = granularity.min(chars.len());
+        let chunk = chars.len().div_ceil(granularity);
+        let smaller = (0..chars.len()).step_by(chunk).find_map(|start| {
+            let end = (start + chunk).min(chars.len());
+            let candidate: String = chars[..start].iter().chain(&chars[end..]).collect();
+            if fails(&candidate) {
+                Some(candidate.chars().collect())
+            } else {
+                None
+            }
+        });
+        match smaller {
+            Some(smaller) => {
+                chars = smaller;
+                granularity = (granularity - 1).max(2);
+            }
+            None if granularity == chars.len() => break,
+            None => granularity *= 2,
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// A minimal input on which raw lexers `A` and `B` disagree, shrunk from `input`,
+/// or `None` if they agree on `input`.
+///
+/// The lexers disagree if they recover differently, which includes any difference in `lex_all`.
+pub fn raw_disagreement<A: raw::Lexer, B: raw::Lexer>(input: &str) -> Option<String> {
+    let disagree = |s: &str| A::lex_all_recovering(s) != B::lex_all_recovering(s);
+    if disagree(input) {
+        Some(minimize(input, disagree))
+    } else {
+        None
+    }
+}
+
+/// A minimal input on which cooked lexers `A` and `B` disagree, shrunk from `input`,
+/// or `None` if they agree on `input`.
+pub fn cooked_disagreement<A: cooked::Lexer, B: cooked::Lexer>(input: &str) -> Option<String> {
+    let disagree = |s: &str| A::lex_all(s) != B::lex_all(s);
+    if disagree(input) {
+        Some(minimize(input, disagree))
+    } else {
+        None
+    }
+}
+
+/// `input` as a case for `tests/cases/*.txt`, whose expected output `BLESS=1` fills in.
+pub fn test_case(input: &str) -> String {
+    let escaped = format!("{:?}", input);
+    format!(
+        "===\n--- input escaped\n{}\n",
+        &escaped[1..escaped.len() - 1]
+    )
+}
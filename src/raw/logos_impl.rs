@@ -1,138 +1,84 @@
-use logos::Logos;
+use {
+    super::{LexError, LexErrorKind, LexErrorKind::*},
+    logos::Logos,
+};
 
-#[allow(nonstandard_style)]
-#[derive(Logos, Debug, Eq, PartialEq)]
-#[logos(
-    subpattern quote_escape = r#"\\['"]"#,
-    subpattern named_escape = r#"\\[nrt\\0]"#,
-    subpattern ascii_escape = r#"\\x[0-7][0-9a-fA-F]"#,
-    subpattern byte_escape = r#"\\x[0-9a-fA-F][0-9a-fA-F]"#,
-    subpattern unicode_escape = r#"\\u\{(?:10|[0-9])[0-9a-fA-F]?[0-9a-fA-F]?[0-9a-fA-F]?[0-9a-fA-F]?\}"#,
-    subpattern indent_escape = r#"\\\n\p{Pattern_White_Space}*"#,
-)]
-enum TokenKind {
-    #[regex(r"//[^\n]*")]
-    line_comment,
-    #[regex(r"\p{Pattern_White_Space}+")]
-    whitespace,
-    #[regex(r"[_\p{XID_Start}]\p{XID_Continue}*")]
-    identifier,
-    #[regex(r"r#[_\p{XID_Start}]\p{XID_Continue}*")]
-    raw_identifier,
-    #[regex(r"[\p{XID_Continue}--_0-9\p{XID_Start}]\p{XID_Continue}*")]
-    identifier_fragment,
-    #[regex(r"'[_\p{XID_Start}]\p{XID_Continue}*")]
-    lifetime,
-    #[regex(r"0b[_0-9]*")]
-    binary_integer,
-    #[regex(r"0b[_0-9]*[eE][+-]?[_0-9]*")]
-    binary_float,
-    #[regex(r"0o[_0-9]*")]
-    octal_integer,
-    #[regex(r"0o[_0-9]*[eE][+-]?[_0-9]*")]
-    octal_float,
-    #[regex(r"0x[_0-9a-fA-F]*")]
-    hexadecimal_integer,
-    #[regex(r"[0-9][_0-9]*")]
-    decimal_integer,
-    #[regex(r"[0-9][_0-9]*[eE][+-]?[_0-9]*")]
-    decimal_float,
-    #[regex(r#"'(?:[^\t\n\r\\']|(?&quote_escape)|(?&named_escape)|(?&ascii_escape)|(?&unicode_escape))'"#)]
-    character,
-    #[regex(r#"b'(?:[[:ascii:]--\t\n\r\\']|(?&quote_escape)|(?&named_escape)|(?&byte_escape))'"#)]
-    byte,
-    #[regex(r#""(?:[^\t\n\r\\"]|\r\n|(?&indent_escape)|(?&quote_escape)|(?&named_escape)|(?&ascii_escape)|(?&unicode_escape))*""#)]
-    string,
-    #[regex(r#"b"(?:[[:ascii:]--\r\\"]|\r\n|(?&indent_escape)|(?&quote_escape)|(?&named_escape)|(?&byte_escape))*""#)]
-    byte_string,
-    #[regex(r"!")]
-    exclamation,
-    #[regex(r"#")]
-    pound,
-    #[regex(r"\$")]
-    dollar,
-    #[regex(r"%")]
-    percent,
-    #[regex(r"&")]
-    ampersand,
-    #[regex(r"\(")]
-    open_parenthesis,
-    #[regex(r"\)")]
-    close_parenthesis,
-    #[regex(r"\*")]
-    star,
-    #[regex(r"\+")]
-    plus,
-    #[regex(r",")]
-    comma,
-    #[regex(r"-")]
-    minus,
-    #[regex(r"\.")]
-    dot,
-    #[regex(r"/")]
-    slash,
-    #[regex(r":")]
-    colon,
-    #[regex(r";")]
-    semicolon,
-    #[regex(r"<")]
-    less,
-    #[regex(r"=")]
-    equal,
-    #[regex(r">")]
-    greater,
-    #[regex(r"\?")]
-    question,
-    #[regex(r"@")]
-    at,
-    #[regex(r"\[")]
-    open_bracket,
-    #[regex(r"\]")]
-    close_bracket,
-    #[regex(r"\^")]
-    circumflex,
-    #[regex(r"\{")]
-    open_brace,
-    #[regex(r"\|")]
-    bar,
-    #[regex(r"\}")]
-    close_brace,
-    #[regex(r"~")]
-    tilde,
-    #[regex(r"/\*", parse_block_comment)]
-    block_comment,
-    #[regex(r#"r[#"]"#, parse_raw_string)]
-    raw_string,
-    #[regex(r#"br[#"]"#, parse_raw_byte_string)]
-    raw_byte_string,
-    #[error]
-    Error,
+macro_rules! token_kind {
+    ($($class:ident = $spec_regex:literal / $regex:tt $(=> $recognizer:ident)?;)*) => {
+        #[allow(nonstandard_style)]
+        #[derive(Logos, Debug, Eq, PartialEq)]
+        #[logos(extras = Option<LexError>)]
+        enum TokenKind {
+            $(
+                #[regex($regex $(, $recognizer)?)]
+                $class,
+            )*
+            /// Matches wherever a `byte_string` may fail, standing for the identifier `b`,
+            /// as logos does not backtrack out of the byte string's repetition to that match.
+            #[regex(r#"b"(?:[^"\\\x{80}-\x{10FFFF}]|[^"\\\x00-\x7F]|\\(?:[\x00-\x7F]|[^\x00-\x7F]))*\\?"#)]
+            byte_string_prefix,
+            /// Matches a raw string fence `r#` followed by a character that can neither
+            /// continue it nor start a raw identifier, which logos misreads as an identifier
+            /// when it fails partway through a raw identifier's multibyte first character.
+            #[regex(
+                r#"r#(?:[^#"_\p{XID_Start}\x{80}-\x{10FFFF}]|[^#"_\p{XID_Start}\x00-\x7F])"#,
+                invalid_raw_string_fence
+            )]
+            invalid_raw_string_fence,
+            #[error]
+            Error,
+        }
+    };
 }
 
+raw_classes!(token_kind);
+
 pub struct Lexer;
 
 impl super::Lexer for Lexer {
-    fn lex(s: &str) -> Result<super::Token, ()> {
-        let lexer = TokenKind::lexer(s).spanned();
-        let (kind, span) = { lexer }.next().ok_or(())?;
+    fn lex(s: &str) -> Result<super::Token, LexError> {
+        let mut lexer = TokenKind::lexer(s);
+        let kind = lexer
+            .next()
+            .ok_or_else(|| LexError::new(UnknownStartOfToken, 0))?;
+        let span = lexer.span();
         assert_eq!(span.start, 0);
+        if let Some(err) = lexer.extras {
+            return Err(err);
+        }
+        let (kind, length) = match kind {
+            TokenKind::byte_string_prefix => (TokenKind::identifier, 1),
+            kind => (kind, span.end),
+        };
         super::Class::ALL
             .get(kind as usize)
-            .ok_or(())
-            .map(|&class| super::Token {
-                class,
-                length: span.end,
-            })
+            .ok_or_else(|| LexError::new(UnknownStartOfToken, 0))
+            .map(|&class| super::Token { class, length })
     }
 }
 
-fn parse_block_comment(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'static str> {
+/// Records why a nonregular recognizer failed at the current position,
+/// as logos drops the error returned from a callback.
+fn fail(lex: &mut logos::Lexer<TokenKind>, kind: LexErrorKind) -> LexErrorKind {
+    lex.extras = Some(LexError::new(kind, lex.span().end));
+    kind
+}
+
+fn invalid_raw_string_fence(lex: &mut logos::Lexer<TokenKind>) -> Result<(), LexErrorKind> {
+    lex.extras = Some(LexError::new(InvalidRawStringFence, 2));
+    Err(InvalidRawStringFence)
+}
+
+fn parse_block_comment(lex: &mut logos::Lexer<TokenKind>) -> Result<(), LexErrorKind> {
     let mut depth: usize = 1;
     while depth > 0 {
-        let interesting_at = lex
-            .remainder()
-            .find(&['/', '*'][..])
-            .ok_or("exhausted source in block comment")?;
+        let interesting_at = match lex.remainder().find(&['/', '*'][..]) {
+            Some(at) => at,
+            None => {
+                lex.bump(lex.remainder().len());
+                Err(fail(lex, UnterminatedBlockComment))?
+            }
+        };
         lex.bump(interesting_at);
         if lex.remainder().starts_with("/*") {
             lex.bump(2);
@@ -147,7 +93,7 @@ fn parse_block_comment(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'static
     Ok(())
 }
 
-fn parse_raw_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'static str> {
+fn parse_raw_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), LexErrorKind> {
     let hash_count: usize = match lex.slice() {
         r#"r""# => 0,
         r#"r#"# => {
@@ -162,13 +108,13 @@ fn parse_raw_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'static st
                         lex.bump(1);
                         break;
                     }
-                    Some(_) => Err("invalid char in raw string opening fence")?,
-                    None => Err("exhausted source in raw string")?,
+                    Some(_) => Err(fail(lex, InvalidRawStringFence))?,
+                    None => Err(fail(lex, UnterminatedRawString))?,
                 }
             }
             hashes
         }
-        _ => Err("unreachable")?,
+        _ => unreachable!(),
     };
 
     loop {
@@ -189,20 +135,17 @@ fn parse_raw_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'static st
                     }
                 }
             }
-            Some('\r') => {
-                lex.bump(1);
-                match lex.remainder().chars().next() {
-                    Some('\n') => lex.bump(1),
-                    _ => Err("bare CR not allowed in raw string")?,
-                }
-            }
+            Some('\r') => match lex.remainder()[1..].chars().next() {
+                Some('\n') => lex.bump(2),
+                _ => Err(fail(lex, BareCrInRawString))?,
+            },
             Some(c) => lex.bump(c.len_utf8()),
-            None => Err("exhausted source in raw string")?,
+            None => Err(fail(lex, UnterminatedRawString))?,
         }
     }
 }
 
-fn parse_raw_byte_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'static str> {
+fn parse_raw_byte_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), LexErrorKind> {
     let hash_count: usize = match lex.slice() {
         r#"br""# => 0,
         r#"br#"# => {
@@ -217,13 +160,13 @@ fn parse_raw_byte_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'stat
                         lex.bump(1);
                         break;
                     }
-                    Some(_) => Err("invalid char in raw string opening fence")?,
-                    None => Err("exhausted source in raw string")?,
+                    Some(_) => Err(fail(lex, InvalidRawByteStringFence))?,
+                    None => Err(fail(lex, UnterminatedRawByteString))?,
                 }
             }
             hashes
         }
-        _ => Err("unreachable")?,
+        _ => unreachable!(),
     };
 
     loop {
@@ -244,15 +187,13 @@ fn parse_raw_byte_string(lex: &mut logos::Lexer<TokenKind>) -> Result<(), &'stat
                     }
                 }
             }
-            Some('\r') => {
-                lex.bump(1);
-                match lex.remainder().chars().next() {
-                    Some('\n') => lex.bump(1),
-                    _ => Err("bare CR not allowed in raw string")?,
-                }
-            }
-            Some(c) => lex.bump(c.len_utf8()),
-            None => Err("exhausted source in raw string")?,
+            Some('\r') => match lex.remainder()[1..].chars().next() {
+                Some('\n') => lex.bump(2),
+                _ => Err(fail(lex, BareCrInRawByteString))?,
+            },
+            Some(c) if c.is_ascii() => lex.bump(1),
+            Some(_) => Err(fail(lex, NonAsciiInRawByteString))?,
+            None => Err(fail(lex, UnterminatedRawByteString))?,
         }
     }
 }
@@ -1,139 +1,314 @@
+// `Class` and the `raw_classes!` macro are generated by build.rs from spec/raw-classes.txt.
+include!(concat!(env!("OUT_DIR"), "/raw_classes.rs"));
+
 #[cfg(feature = "logos")]
 pub mod logos_impl;
 pub mod reference_impl;
 
-#[allow(nonstandard_style)]
+use std::{fmt, iter::FusedIterator, marker::PhantomData, ops::Range};
+
+/// A raw token, of a lexical class unless `C` says otherwise.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum Class {
-    line_comment,
-    whitespace,
-    identifier,
-    raw_identifier,
-    identifier_fragment,
-    lifetime,
-    binary_integer,
-    binary_float,
-    octal_integer,
-    octal_float,
-    hexadecimal_integer,
-    decimal_integer,
-    decimal_float,
-    character,
-    byte,
-    string,
-    byte_string,
-    exclamation,
-    pound,
-    dollar,
-    percent,
-    ampersand,
-    open_parenthesis,
-    close_parenthesis,
-    star,
-    plus,
-    comma,
-    minus,
-    dot,
-    slash,
-    colon,
-    semicolon,
-    less,
-    equal,
-    greater,
-    question,
-    at,
-    open_bracket,
-    close_bracket,
-    circumflex,
-    open_brace,
-    bar,
-    close_brace,
-    tilde,
-    block_comment,
-    raw_string,
-    raw_byte_string,
-}
-pub use Class::*;
-
-impl Class {
-    pub const COUNT: usize = raw_byte_string as usize + 1;
-    pub const ALL: [Class; Class::COUNT] = [
-        line_comment,
-        whitespace,
-        identifier,
-        raw_identifier,
-        identifier_fragment,
-        lifetime,
-        binary_integer,
-        binary_float,
-        octal_integer,
-        octal_float,
-        hexadecimal_integer,
-        decimal_integer,
-        decimal_float,
-        character,
-        byte,
-        string,
-        byte_string,
-        exclamation,
-        pound,
-        dollar,
-        percent,
-        ampersand,
-        open_parenthesis,
-        close_parenthesis,
-        star,
-        plus,
-        comma,
-        minus,
-        dot,
-        slash,
-        colon,
-        semicolon,
-        less,
-        equal,
-        greater,
-        question,
-        at,
-        open_bracket,
-        close_bracket,
-        circumflex,
-        open_brace,
-        bar,
-        close_brace,
-        tilde,
-        block_comment,
-        raw_string,
-        raw_byte_string,
-    ];
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token<C = Class> {
+    pub class: C,
+    pub length: usize,
 }
 
+/// A token from [`Lexer::lex_all_recovering`]: either of a lexical class,
+/// or source skipped after an error, along with why lexing failed there.
+pub type RecoveredToken = Token<Result<Class, LexError>>;
+
+impl<C: Copy> Token<C> {
+    /// Places `tokens`, which were lexed from the start of `src`, back into `src`.
+    pub fn spanned<'src>(
+        src: &'src str,
+        tokens: impl IntoIterator<Item = Token<C>>,
+    ) -> impl Iterator<Item = SpannedToken<'src, C>> {
+        let mut start = 0;
+        tokens.into_iter().map(move |token| {
+            let spanned = SpannedToken::new(src, start, token);
+            start += token.length;
+            spanned
+        })
+    }
+}
+
+/// A raw token along with where it is in the source.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub struct Token {
-    pub class: Class,
-    pub length: usize,
+pub struct SpannedToken<'src, C = Class> {
+    pub class: C,
+    /// Byte offset of the start of the token in the source.
+    pub start: usize,
+    /// The source text of the token.
+    pub text: &'src str,
+}
+
+impl<'src, C: Copy> SpannedToken<'src, C> {
+    pub(crate) fn new(src: &'src str, start: usize, token: Token<C>) -> Self {
+        SpannedToken {
+            class: token.class,
+            start,
+            text: &src[start..start + token.length],
+        }
+    }
+
+    /// Byte offset of the end of the token in the source.
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end()
+    }
+
+    pub fn token(&self) -> Token<C> {
+        Token {
+            class: self.class,
+            length: self.text.len(),
+        }
+    }
 }
 
-pub trait Lexer {
-    fn lex(s: &str) -> Result<Token, ()>;
-    fn lex_all(s: &str) -> Result<Vec<Token>, ()> {
-        let mut s = s;
-        std::iter::from_fn(|| {
-            if s.is_empty() {
-                return None;
-            } else {
-                match Self::lex(s) {
-                    Ok(token) => {
-                        s = &s[token.length..];
-                        Some(Ok(token))
-                    }
-                    Err(()) => {
-                        s = "";
-                        Some(Err(()))
-                    }
+/// Why the raw lexer failed to produce a token.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    /// Byte offset into the lexed source where the failure was found.
+    pub offset: usize,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LexErrorKind {
+    /// No lexical class matches a prefix of the source.
+    UnknownStartOfToken,
+    /// More than one lexical class matches and no tie rule prefers one of them.
+    UnresolvedTie,
+    /// A string literal has no closing quote anywhere in the source.
+    UnterminatedString,
+    /// A byte string literal has no closing quote anywhere in the source.
+    UnterminatedByteString,
+    /// A character literal, which is not also a lifetime,
+    /// has no closing quote anywhere in the source.
+    UnterminatedCharacter,
+    UnterminatedBlockComment,
+    InvalidRawStringFence,
+    UnterminatedRawString,
+    BareCrInRawString,
+    InvalidRawByteStringFence,
+    UnterminatedRawByteString,
+    BareCrInRawByteString,
+    NonAsciiInRawByteString,
+}
+pub use LexErrorKind::*;
+
+impl LexError {
+    pub fn new(kind: LexErrorKind, offset: usize) -> Self {
+        LexError { kind, offset }
+    }
+
+    /// The error for a string or byte string literal at the start of `s`,
+    /// or a character literal if `or_character`, whose closing quote is nowhere in `s`.
+    ///
+    /// The error is at the end of `s`, so that the literal runs to the end of the source.
+    /// Recovery checks for string literals before lexing, as `b` alone is an identifier,
+    /// but for character literals only once no class matches, as `'a` alone is a lifetime.
+    pub(crate) fn unterminated_literal(s: &str, or_character: bool) -> Option<LexError> {
+        let (kind, quote, body) = if let Some(body) = s.strip_prefix('"') {
+            (UnterminatedString, '"', body)
+        } else if let Some(body) = s.strip_prefix("b\"") {
+            (UnterminatedByteString, '"', body)
+        } else if let (true, Some(body)) = (or_character, s.strip_prefix('\'')) {
+            (UnterminatedCharacter, '\'', body)
+        } else {
+            return None;
+        };
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
                 }
+                c if c == quote => return None,
+                _ => {}
             }
+        }
+        Some(LexError::new(kind, s.len()))
+    }
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnknownStartOfToken => "unknown start of token",
+            UnresolvedTie => "no tie rule for the matching lexical classes",
+            UnterminatedString => "exhausted source in string",
+            UnterminatedByteString => "exhausted source in byte string",
+            UnterminatedCharacter => "exhausted source in character",
+            UnterminatedBlockComment => "exhausted source in block comment",
+            InvalidRawStringFence => "invalid char in raw string opening fence",
+            UnterminatedRawString => "exhausted source in raw string",
+            BareCrInRawString => "bare CR not allowed in raw string",
+            InvalidRawByteStringFence => "invalid char in raw byte string opening fence",
+            UnterminatedRawByteString => "exhausted source in raw byte string",
+            BareCrInRawByteString => "bare CR not allowed in raw byte string",
+            NonAsciiInRawByteString => "raw byte string must be ASCII",
         })
-        .collect()
     }
 }
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+pub trait Lexer: Sized {
+    fn lex(s: &str) -> Result<Token, LexError>;
+    fn tokenize(s: &str) -> Tokens<'_, Self> {
+        Tokens::new(s)
+    }
+    fn lex_all(s: &str) -> Result<Vec<Token>, LexError> {
+        Self::tokenize(s).collect()
+    }
+
+    /// Lexes all of `s`, skipping each unlexable prefix and resuming after it.
+    ///
+    /// The skipped source runs up to where the failure was found,
+    /// which for a literal with no closing quote is the end of the source,
+    /// or is a single code point if no lexical class matches at all.
+    /// Its error has an offset from the start of `s`.
+    fn lex_all_recovering(s: &str) -> Vec<RecoveredToken> {
+        let mut tokens = vec![];
+        let mut offset = 0;
+        while offset < s.len() {
+            let token = RecoveredToken::lex(s, offset, Self::lex);
+            offset += token.length;
+            tokens.push(token);
+        }
+        tokens
+    }
+}
+
+impl RecoveredToken {
+    /// Lexes the token from `offset` into `s` with `lex`, or the source it skips on failure,
+    /// as [`Lexer::lex_all_recovering`] does.
+    ///
+    /// A string, byte string or character literal with no closing quote is skipped
+    /// to the end of the source, rather than lexed as whatever its quote starts.
+    pub(crate) fn lex(
+        s: &str,
+        offset: usize,
+        lex: impl FnOnce(&str) -> Result<Token, LexError>,
+    ) -> Self {
+        let rest = &s[offset..];
+        let lexed = match LexError::unterminated_literal(rest, false) {
+            Some(err) => Err(err),
+            None => lex(rest).map_err(|err| match err.kind {
+                UnknownStartOfToken => LexError::unterminated_literal(rest, true).unwrap_or(err),
+                _ => err,
+            }),
+        };
+        match lexed {
+            Ok(token) => Token {
+                class: Ok(token.class),
+                length: token.length,
+            },
+            Err(err) => Token {
+                class: Err(LexError::new(err.kind, offset + err.offset)),
+                length: match err.offset {
+                    0 => rest.chars().next().unwrap().len_utf8(),
+                    length => length,
+                },
+            },
+        }
+    }
+}
+
+/// Lazily lexes raw tokens from the front of a source string.
+///
+/// Stops after the first error, whose offset is relative to the start of the source.
+pub struct Tokens<'src, L> {
+    src: &'src str,
+    offset: usize,
+    lexer: PhantomData<fn() -> L>,
+}
+
+impl<'src, L: Lexer> Tokens<'src, L> {
+    pub fn new(src: &'src str) -> Self {
+        Tokens {
+            src,
+            offset: 0,
+            lexer: PhantomData,
+        }
+    }
+
+    /// Byte offset of the next token in the source.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The source which has not been lexed yet.
+    pub fn remainder(&self) -> &'src str {
+        &self.src[self.offset..]
+    }
+
+    /// Yields tokens along with their position in the source.
+    pub fn spanned(self) -> Spanned<'src, L> {
+        Spanned { tokens: self }
+    }
+}
+
+impl<L: Lexer> Iterator for Tokens<'_, L> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset == self.src.len() {
+            return None;
+        }
+        match L::lex(self.remainder()) {
+            Ok(token) => {
+                self.offset += token.length;
+                Some(Ok(token))
+            }
+            Err(err) => {
+                let offset = self.offset + err.offset;
+                self.offset = self.src.len();
+                Some(Err(LexError::new(err.kind, offset)))
+            }
+        }
+    }
+}
+
+impl<L: Lexer> FusedIterator for Tokens<'_, L> {}
+
+/// Lazily lexes [`SpannedToken`]s; see [`Tokens::spanned`].
+pub struct Spanned<'src, L> {
+    tokens: Tokens<'src, L>,
+}
+
+impl<'src, L: Lexer> Spanned<'src, L> {
+    /// Byte offset of the next token in the source.
+    pub fn offset(&self) -> usize {
+        self.tokens.offset()
+    }
+
+    /// The source which has not been lexed yet.
+    pub fn remainder(&self) -> &'src str {
+        self.tokens.remainder()
+    }
+}
+
+impl<'src, L: Lexer> Iterator for Spanned<'src, L> {
+    type Item = Result<SpannedToken<'src>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.tokens.offset();
+        let token = self.tokens.next()?;
+        Some(token.map(|token| SpannedToken::new(self.tokens.src, start, token)))
+    }
+}
+
+impl<L: Lexer> FusedIterator for Spanned<'_, L> {}
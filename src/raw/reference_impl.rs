@@ -1,91 +1,23 @@
+// The recognizers below are copied from the specification.
+#![allow(clippy::needless_return)]
+
 use {
     super::*,
+    crate::coverage,
     once_cell::sync::Lazy,
     regex::{Regex, RegexSet},
-    std::panic::catch_unwind,
 };
 
-static REGEX_SET: Lazy<RegexSet> = Lazy::new(|| {
-    RegexSet::new(vec![
-        r"\A//[^\n]*",                                               // line_comment
-        r"\A\p{Pattern_White_Space}+",                               // whitespace
-        r"\A[_\p{XID_Start}]\p{XID_Continue}*",                      // identifier
-        r"\Ar#[_\p{XID_Start}]\p{XID_Continue}*",                    // raw_identifier
-        r"\A[\p{XID_Continue}--_0-9\p{XID_Start}]\p{XID_Continue}*", // identifier_fragment
-        r"\A'[_\p{XID_Start}]\p{XID_Continue}*",                     // lifetime
-        r"\A0b[_0-9]*",                                              // binary_integer
-        r"\A0b[_0-9]*[eE][+-]?[_0-9]*",                              // binary_float
-        r"\A0o[_0-9]*",                                              // octal_integer
-        r"\A0o[_0-9]*[eE][+-]?[_0-9]*",                              // octal_float
-        r"\A0x[_0-9a-fA-F]*",                                        // hexadecimal_integer
-        r"\A[0-9][_0-9]*",                                           // decimal_integer
-        r"\A[0-9][_0-9]*[eE][+-]?[_0-9]*",                           // decimal_float
-        concat!(
-            r#"\A'(?:[^\t\n\r\\']"#,
-            r#"|\\['"]"#,                              // quote_escape
-            r#"|\\[nrt\\0]"#,                          // named_escape
-            r#"|\\x[0-7][0-9a-fA-F]"#,                 // ascii_escape
-            r#"|\\u\{(?:10|[0-9])[0-9a-fA-F]{0,4}\}"#, // unicode_escape
-            r#")'"#
-        ), // character
-        concat!(
-            r#"\A'(?:[[:ascii:]--\t\n\r\\']"#,
-            r#"|\\['"]"#,            // quote_escape
-            r#"|\\[nrt\\0]"#,        // named_escape
-            r#"|\\x[0-9a-fA-F]{2}"#, // byte_escape
-            r#")'"#
-        ), // byte
-        concat!(
-            r#"\A"(?:[^\t\n\r\\"]"#,
-            r#"|\r\n"#,
-            r#"|\\\n\p{Pattern_White_Space}*"#, // indent_escape
-            r#"|\\['"]"#,                       // quote_escape
-            r#"|\\[nrt\\0]"#,                   // named_escape
-            r#"|\\x[0-7][0-9a-fA-F]"#,          // ascii_escape
-            r#"|\\u\{(?:10|[0-9])[0-9a-fA-F]{0,4}\}"#, // unicode_escape
-            r#")*""#
-        ), // string
-        concat!(
-            r#"\Ab"(?:[[:ascii:]--\r\\"]"#,
-            r#"|\r\n"#,
-            r#"|\\\n\p{Pattern_White_Space}*"#, // indent_escape
-            r#"|\\['"]"#,                       // quote_escape
-            r#"|\\[nrt\\0]"#,                   // named_escape
-            r#"|\\x[0-9a-fA-F]{2}"#,            // byte_escape
-            r#")*""#
-        ), // byte_string
-        r"\A!",                                                      // exclamation
-        r"\A#",                                                      // pound
-        r"\A\$",                                                     // dollar
-        r"\A%",                                                      // percent
-        r"\A&",                                                      // ampersand
-        r"\A\(",                                                     // open_parenthesis
-        r"\A\)",                                                     // close_parenthesis
-        r"\A\*",                                                     // star
-        r"\A\+",                                                     // plus
-        r"\A,",                                                      // comma
-        r"\A-",                                                      // minus
-        r"\A\.",                                                     // dot
-        r"\A/",                                                      // slash
-        r"\A:",                                                      // colon
-        r"\A;",                                                      // semicolon
-        r"\A<",                                                      // less
-        r"\A=",                                                      // equal
-        r"\A>",                                                      // greater
-        r"\A\?",                                                     // question
-        r"\A@",                                                      // at
-        r"\A\[",                                                     // open_bracket
-        r"\A\]",                                                     // close_bracket
-        r"\A\^",                                                     // circumflex
-        r"\A\{",                                                     // open_brace
-        r"\A\|",                                                     // bar
-        r"\A\}",                                                     // close_brace
-        r"\A~",                                                      // tilde
-        r"\A/\*",                                                    // block_comment
-        r#"\Ar[#"]"#,                                                // raw_string
-        r#"\Abr[#"]"#,                                               // raw_byte_string
-    ])
-    .unwrap()
+macro_rules! regex_set {
+    ($($class:ident = $regex:literal / $logos_regex:literal $(=> $recognizer:ident)?;)*) => {
+        [$(($class, concat!(r"\A", $regex))),*]
+    };
+}
+
+/// The regular expression of each lexical class, in the order of [`Class::ALL`].
+pub static REGEX_SET: Lazy<RegexSet> = Lazy::new(|| {
+    let classes: [(Class, &str); Class::COUNT] = raw_classes!(regex_set);
+    RegexSet::new(classes.iter().map(|&(_, regex)| regex)).unwrap()
 });
 
 static ALL_REGEX: Lazy<Vec<Regex>> = Lazy::new(|| {
@@ -96,45 +28,170 @@ static ALL_REGEX: Lazy<Vec<Regex>> = Lazy::new(|| {
     vec
 });
 
-pub struct Lexer;
+macro_rules! ties {
+    ($([$($class:ident),+] => $preferred:ident,)*) => {
+        &[$((concat!("tie.", stringify!($preferred)), &[$($class),+], $preferred),)*]
+    };
+}
 
-impl super::Lexer for Lexer {
-    fn lex(s: &str) -> Result<Token, ()> {
-        let matches: Vec<Class> = REGEX_SET
-            .matches(s)
+/// Each tie rule by ID, as the classes whose regular expressions all match
+/// in the order of [`Class::ALL`], and the class it prefers.
+pub static TIES: &[(&str, &[Class], Class)] = ties! {
+    [line_comment, slash] => line_comment,
+    [binary_integer, decimal_integer] => binary_integer,
+    [binary_integer, binary_float, decimal_integer] => binary_float,
+    [octal_integer, decimal_integer] => octal_integer,
+    [octal_integer, octal_float, decimal_integer] => octal_float,
+    [hexadecimal_integer, decimal_integer] => hexadecimal_integer,
+    [decimal_integer, decimal_float] => decimal_float,
+    [lifetime, character] => character,
+    [identifier, byte] => byte,
+    [identifier, byte_string] => byte_string,
+    [slash, block_comment] => block_comment,
+    [identifier, raw_string] => raw_string,
+    [identifier, raw_byte_string] => raw_byte_string,
+    [identifier, raw_identifier, raw_string] => raw_identifier,
+};
+
+/// The ID of each outcome of the recognizers below, by the class recognized
+/// and the kind of error returned, or `None` if the token was accepted.
+pub static BRANCHES: &[(&str, Class, Option<LexErrorKind>)] = &[
+    ("block_comment.closed", block_comment, None),
+    (
+        "block_comment.unterminated",
+        block_comment,
+        Some(UnterminatedBlockComment),
+    ),
+    ("raw_string.closed", raw_string, None),
+    (
+        "raw_string.invalid_fence",
+        raw_string,
+        Some(InvalidRawStringFence),
+    ),
+    (
+        "raw_string.unterminated",
+        raw_string,
+        Some(UnterminatedRawString),
+    ),
+    ("raw_string.bare_cr", raw_string, Some(BareCrInRawString)),
+    ("raw_byte_string.closed", raw_byte_string, None),
+    (
+        "raw_byte_string.invalid_fence",
+        raw_byte_string,
+        Some(InvalidRawByteStringFence),
+    ),
+    (
+        "raw_byte_string.unterminated",
+        raw_byte_string,
+        Some(UnterminatedRawByteString),
+    ),
+    (
+        "raw_byte_string.bare_cr",
+        raw_byte_string,
+        Some(BareCrInRawByteString),
+    ),
+    (
+        "raw_byte_string.non_ascii",
+        raw_byte_string,
+        Some(NonAsciiInRawByteString),
+    ),
+];
+
+/// The classes whose regular expressions match a prefix of `s`, in the order of [`Class::ALL`].
+pub fn matches(s: &str) -> Vec<Class> {
+    REGEX_SET
+        .matches(s)
+        .iter()
+        .map(|ix| Class::ALL[ix])
+        .collect()
+}
+
+/// The ID of the tie rule for when exactly the regular expressions of `matches` match,
+/// and the class it prefers.
+///
+/// `matches` is in the order of [`Class::ALL`].
+pub fn tie_rule(matches: &[Class]) -> Option<(&'static str, Class)> {
+    match matches {
+        &[any] => Some(("tie.single", any)),
+        _ => TIES
             .iter()
-            .map(|ix| Class::ALL[ix])
-            .collect();
-        let class = match &*matches {
-            [] => Err(())?,
-            &[any] => any,
-            [line_comment, slash] => line_comment,
-            [binary_integer, decimal_integer] => binary_integer,
-            [binary_integer, binary_float, decimal_integer] => binary_float,
-            [octal_integer, decimal_integer] => octal_integer,
-            [octal_integer, octal_float, decimal_integer] => octal_float,
-            [hexadecimal_integer, decimal_integer] => hexadecimal_integer,
-            [decimal_integer, decimal_float] => decimal_float,
-            [lifetime, character] => character,
-            [identifier, byte_string] => byte_string,
-            [identifier, identifier_fragment] => identifier,
-            [slash, block_comment] => block_comment,
-            [identifier, raw_string] => raw_string,
-            [identifier, raw_byte_string] => raw_byte_string,
-            other => unimplemented!("raw lexer matched set {:?}", other),
+            .find(|&&(_, classes, _)| classes == matches)
+            .map(|&(id, _, preferred)| (id, preferred)),
+    }
+}
+
+/// The class a tie rule prefers when exactly the regular expressions of `matches` match.
+///
+/// `matches` is in the order of [`Class::ALL`].
+pub fn tie(matches: &[Class]) -> Option<Class> {
+    tie_rule(matches).map(|(_, class)| class)
+}
+
+/// How [`Lexer`] picked the class of a token, or failed to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Decision {
+    /// The classes whose regular expressions match a prefix of the source,
+    /// in the order of [`Class::ALL`].
+    pub matches: Vec<Class>,
+    /// The ID of the rule which picked the class or failed, if one did.
+    pub rule: Option<&'static str>,
+    /// The class picked, whose regular expression or recognizer found the end of the token.
+    pub class: Option<Class>,
+}
+
+pub struct Lexer;
+
+impl Lexer {
+    /// Lexes a token from the front of `s` as [`super::Lexer::lex`] does,
+    /// along with how its class was picked.
+    pub fn lex_deciding(s: &str) -> (Result<Token, LexError>, Decision) {
+        let mut decision = Decision {
+            matches: matches(s),
+            rule: None,
+            class: None,
+        };
+        let token = Self::decide(s, &mut decision);
+        if let Some(rule) = decision.rule {
+            coverage::hit(rule);
+        }
+        (token, decision)
+    }
+
+    fn decide(s: &str, decision: &mut Decision) -> Result<Token, LexError> {
+        let class = match tie_rule(&decision.matches) {
+            Some((rule, class)) => {
+                decision.rule = Some(rule);
+                decision.class = Some(class);
+                class
+            }
+            None if decision.matches.is_empty() => {
+                decision.rule = Some("lex.unknown_start");
+                Err(LexError::new(UnknownStartOfToken, 0))?
+            }
+            None => Err(LexError::new(UnresolvedTie, 0))?,
+        };
+        let recognized = |length: Result<usize, LexError>| {
+            let outcome = length.as_ref().err().map(|err| err.kind);
+            let branch = BRANCHES
+                .iter()
+                .find(|&&(_, recognizer, kind)| recognizer == class && kind == outcome);
+            if let Some(&(id, ..)) = branch {
+                coverage::hit(id);
+            }
+            length
         };
         Ok(match class {
             block_comment => Token {
                 class,
-                length: catch_unwind(|| parse_block_comment(s)).map_err(drop)?,
+                length: recognized(parse_block_comment(s))?,
             },
             raw_string => Token {
                 class,
-                length: catch_unwind(|| parse_raw_string(s)).map_err(drop)?,
+                length: recognized(parse_raw_string(s))?,
             },
             raw_byte_string => Token {
                 class,
-                length: catch_unwind(|| parse_raw_byte_string(s)).map_err(drop)?,
+                length: recognized(parse_raw_byte_string(s))?,
             },
             _ => {
                 let mat = ALL_REGEX[class as usize].find(s).unwrap();
@@ -148,10 +205,16 @@ impl super::Lexer for Lexer {
     }
 }
 
+impl super::Lexer for Lexer {
+    fn lex(s: &str) -> Result<Token, LexError> {
+        Self::lex_deciding(s).0
+    }
+}
+
 // Reminder: these functions are copied from the specification.
 // Do not optimize or make idiomatic; their purpose is to be obvious.
 
-pub fn parse_block_comment(s: &str) -> usize {
+pub fn parse_block_comment(s: &str) -> Result<usize, LexError> {
     let mut chars = s.chars().peekable();
     assert_eq!(chars.next(), Some('/'));
     assert_eq!(chars.next(), Some('*'));
@@ -172,14 +235,14 @@ pub fn parse_block_comment(s: &str) -> usize {
                 len += 2;
             }
             Some(c) => len += c.len_utf8(),
-            None => panic!("exhausted source in block comment"),
+            None => return Err(LexError::new(UnterminatedBlockComment, len)),
         }
     }
 
-    return len;
+    return Ok(len);
 }
 
-fn parse_raw_string(s: &str) -> usize {
+fn parse_raw_string(s: &str) -> Result<usize, LexError> {
     let mut chars = s.chars().peekable();
     assert_eq!(chars.next(), Some('r'));
 
@@ -196,8 +259,8 @@ fn parse_raw_string(s: &str) -> usize {
                 len += 1;
                 break;
             }
-            Some(c) => panic!("invalid char {:?} in raw string opening fence", c),
-            None => panic!("exhausted source in raw string"),
+            Some(_) => return Err(LexError::new(InvalidRawStringFence, len)),
+            None => return Err(LexError::new(UnterminatedRawString, len)),
         }
     }
 
@@ -208,7 +271,7 @@ fn parse_raw_string(s: &str) -> usize {
                 let mut hashes_seen: usize = 0;
                 loop {
                     if hashes_seen == hashes {
-                        return len;
+                        return Ok(len);
                     }
                     match chars.peek() {
                         Some('#') => {
@@ -222,15 +285,15 @@ fn parse_raw_string(s: &str) -> usize {
             }
             Some('\r') => match chars.next() {
                 Some('\n') => len += 2,
-                _ => panic!("bare CR not allowed in raw string"),
+                _ => return Err(LexError::new(BareCrInRawString, len)),
             },
             Some(c) => len += c.len_utf8(),
-            None => panic!("exhausted source in raw string"),
+            None => return Err(LexError::new(UnterminatedRawString, len)),
         }
     }
 }
 
-fn parse_raw_byte_string(s: &str) -> usize {
+fn parse_raw_byte_string(s: &str) -> Result<usize, LexError> {
     let mut chars = s.chars().peekable();
     assert_eq!(chars.next(), Some('b'));
     assert_eq!(chars.next(), Some('r'));
@@ -248,8 +311,8 @@ fn parse_raw_byte_string(s: &str) -> usize {
                 len += 1;
                 break;
             }
-            Some(c) => panic!("invalid char {:?} in raw byte string opening fence", c),
-            None => panic!("exhausted source in raw byte string"),
+            Some(_) => return Err(LexError::new(InvalidRawByteStringFence, len)),
+            None => return Err(LexError::new(UnterminatedRawByteString, len)),
         }
     }
 
@@ -260,7 +323,7 @@ fn parse_raw_byte_string(s: &str) -> usize {
                 let mut hashes_seen: usize = 0;
                 loop {
                     if hashes_seen == hashes {
-                        return len;
+                        return Ok(len);
                     }
                     match chars.peek() {
                         Some('#') => {
@@ -274,11 +337,11 @@ fn parse_raw_byte_string(s: &str) -> usize {
             }
             Some('\r') => match chars.next() {
                 Some('\n') => len += 2,
-                _ => panic!("bare CR not allowed in raw byte string"),
+                _ => return Err(LexError::new(BareCrInRawByteString, len)),
             },
             Some(c) if c.is_ascii() => len += 1,
-            Some(_) => panic!("raw byte string must be ASCII"),
-            None => panic!("exhausted source in raw byte string"),
+            Some(_) => return Err(LexError::new(NonAsciiInRawByteString, len)),
+            None => return Err(LexError::new(UnterminatedRawByteString, len)),
         }
     }
 }
@@ -0,0 +1,369 @@
+//! Runs every raw lexer and cooked lexer on the cases in `tests/cases/*.txt`.
+//!
+//! Each case is a block of sections:
+//!
+//! ```text
+//! # Comments, before the `===` line only.
+//! === a description of the case
+//! --- input
+//! let s = "verbatim";
+//! --- raw
+//! identifier "let"
+//! whitespace " "
+//! ...
+//! --- cooked
+//! Identifier_Plain "let"
+//! ...
+//! ```
+//!
+//! The input is taken verbatim, up to the next `---` or `===` line and without trailing blank lines.
+//! An input which cannot be written that way, such as one with a `\r` or a trailing newline,
+//! is given as `--- input escaped` followed by one line with the escapes of a Rust string literal.
+//!
+//! `raw` and `cooked` are the output of the reference implementations:
+//! each raw token and its text from `lex_all_recovering`, or `error`, the source skipped and the kind of error,
+//! and each cooked token and its text, with how many raw tokens it was cooked from if not one,
+//! or the error that stopped cooking.
+//! Every other implementation must produce the same output,
+//! unless the case has a section such as `--- cooked proc_macro2_impl` with what it produces instead.
+//!
+//! Run with `BLESS=1` to rewrite the expected output of every case from what each implementation produces.
+//!
+//! With the `coverage` feature, the cases must also apply every rule of the reference implementations
+//! at least once, by the IDs of `lexical_specification::coverage`.
+
+use {
+    lexical_specification::{cooked, raw},
+    std::{env, fmt::Write as _, fs, path::Path},
+};
+
+type Render = fn(&str) -> Vec<String>;
+
+/// Each raw lexer by name, the reference first.
+fn raw_impls() -> Vec<(&'static str, Render)> {
+    vec![
+        ("reference_impl", render_raw::<raw::reference_impl::Lexer>),
+        #[cfg(feature = "logos")]
+        ("logos_impl", render_raw::<raw::logos_impl::Lexer>),
+    ]
+}
+
+/// Each cooked lexer by name, the reference first.
+fn cooked_impls() -> Vec<(&'static str, Render)> {
+    vec![
+        (
+            "reference_impl",
+            render_cooked::<
+                cooked::Pipeline<raw::reference_impl::Lexer, cooked::reference_impl::Cooker>,
+            >,
+        ),
+        ("rustc_impl", render_cooked::<cooked::rustc_impl::Lexer>),
+        #[cfg(feature = "proc_macro2")]
+        (
+            "proc_macro2_impl",
+            render_cooked::<
+                cooked::Pipeline<raw::reference_impl::Lexer, cooked::proc_macro2_impl::Cooker>,
+            >,
+        ),
+    ]
+}
+
+fn render_raw<L: raw::Lexer>(src: &str) -> Vec<String> {
+    raw::Token::spanned(src, L::lex_all_recovering(src))
+        .map(|token| match token.class {
+            Ok(class) => format!("{:?} {:?}", class, token.text),
+            Err(err) => format!("error {:?} {:?}", token.text, err.kind),
+        })
+        .collect()
+}
+
+fn render_cooked<L: cooked::Lexer>(src: &str) -> Vec<String> {
+    match L::lex_all(src) {
+        Ok(tokens) => cooked::Token::spanned(src, tokens)
+            .map(|token| match token.raw_tokens {
+                1 => format!("{:?} {:?}", token.class, token.text),
+                n => format!("{:?} {:?} ({} raw tokens)", token.class, token.text, n),
+            })
+            .collect(),
+        Err(err) => vec![format!("ERROR {}", err)],
+    }
+}
+
+struct Case {
+    /// Line number of the `===` line.
+    line: usize,
+    comments: Vec<String>,
+    description: String,
+    input: String,
+    /// Each section of expected output, by name, in order.
+    expected: Vec<(String, Vec<String>)>,
+}
+
+impl Case {
+    fn expected(&self, section: &str) -> Option<&[String]> {
+        self.expected
+            .iter()
+            .find(|(name, _)| name == section)
+            .map(|(_, lines)| &**lines)
+    }
+}
+
+fn parse(path: &Path, text: &str) -> Vec<Case> {
+    let mut cases: Vec<Case> = vec![];
+    let mut comments = vec![];
+    // The name of the section being read, or `None` before the first case.
+    let mut section: Option<String> = None;
+    let mut input = vec![];
+    let finish_input = |input: &mut Vec<&str>, case: &mut Case| {
+        while input.last() == Some(&"") {
+            input.pop();
+        }
+        case.input = input.join("\n");
+        input.clear();
+    };
+    for (i, line) in text.lines().enumerate() {
+        let at = || format!("{}:{}", path.display(), i + 1);
+        if section.as_deref() == Some("input")
+            && !line.starts_with("---")
+            && !line.starts_with("===")
+        {
+            input.push(line);
+            continue;
+        }
+        if section.as_deref() == Some("input") {
+            finish_input(&mut input, cases.last_mut().unwrap());
+        }
+        if section.as_deref() == Some("input escaped") {
+            cases.last_mut().unwrap().input =
+                unescape(line).unwrap_or_else(|| panic!("{}: invalid escape", at()));
+            section = Some(String::new());
+        } else if let Some(description) = line.strip_prefix("===") {
+            cases.push(Case {
+                line: i + 1,
+                comments: std::mem::take(&mut comments),
+                description: description.trim().to_string(),
+                input: String::new(),
+                expected: vec![],
+            });
+            section = Some(String::new());
+        } else if let Some(name) = line.strip_prefix("--- ") {
+            let case = cases
+                .last_mut()
+                .unwrap_or_else(|| panic!("{}: section before any case", at()));
+            if name != "input" && name != "input escaped" {
+                case.expected.push((name.to_string(), vec![]));
+            }
+            section = Some(name.to_string());
+        } else if line.starts_with('#') {
+            comments.push(line.to_string());
+        } else if line.is_empty() {
+        } else if let Some((_, lines)) = cases.last_mut().and_then(|case| case.expected.last_mut())
+        {
+            lines.push(line.to_string());
+        } else {
+            panic!("{}: unexpected line {:?}", at(), line);
+        }
+    }
+    if section.as_deref() == Some("input") {
+        finish_input(&mut input, cases.last_mut().unwrap());
+    }
+    cases
+}
+
+/// The string of the escapes of a Rust string literal, without its quotes.
+fn unescape(escaped: &str) -> Option<String> {
+    let mut s = String::new();
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+        s.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            c @ ('\\' | '"' | '\'') => c,
+            'u' => {
+                let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                std::char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            _ => return None,
+        });
+    }
+    Some(s)
+}
+
+/// Whether `input` reads back the same when written verbatim, and shows all of its characters.
+fn is_verbatim(input: &str) -> bool {
+    !input.ends_with('\n')
+        && input.lines().all(|line| {
+            !line.starts_with("---") && !line.starts_with("===") && !line.ends_with(' ')
+        })
+        && input.chars().all(|c| match c {
+            '\n' | '"' | '\'' | '\\' => true,
+            c => c.escape_debug().eq(Some(c)),
+        })
+}
+
+/// `input` as the escapes of a Rust string literal, without its quotes,
+/// with no quote escaped, no leading `#`, `-` or `=` and no trailing space.
+fn escape(input: &str) -> String {
+    let mut escaped = String::new();
+    for (i, c) in input.chars().enumerate() {
+        match c {
+            '#' | '-' | '=' if i == 0 => write!(escaped, "\\u{{{:x}}}", c as u32).unwrap(),
+            '"' | '\'' => escaped.push(c),
+            c => escaped.extend(c.escape_debug()),
+        }
+    }
+    if escaped.ends_with(' ') {
+        escaped.pop();
+        escaped.push_str("\\u{20}");
+    }
+    escaped
+}
+
+fn write(cases: &[Case]) -> String {
+    let mut out = String::new();
+    for (i, case) in cases.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        for comment in &case.comments {
+            writeln!(out, "{}", comment).unwrap();
+        }
+        match &*case.description {
+            "" => out.push_str("===\n"),
+            description => writeln!(out, "=== {}", description).unwrap(),
+        }
+        if is_verbatim(&case.input) {
+            out.push_str("--- input\n");
+            if !case.input.is_empty() {
+                writeln!(out, "{}", case.input).unwrap();
+            }
+        } else {
+            writeln!(out, "--- input escaped\n{}", escape(&case.input)).unwrap();
+        }
+        for (name, lines) in &case.expected {
+            writeln!(out, "--- {}", name).unwrap();
+            for line in lines {
+                writeln!(out, "{}", line).unwrap();
+            }
+        }
+    }
+    out
+}
+
+/// Checks each implementation on `case`, returning what fails,
+/// or rewrites the expected output of `case` if `bless`.
+fn check(case: &mut Case, bless: bool) -> Vec<String> {
+    let mut failures = vec![];
+    let mut blessed = vec![];
+    for (kind, impls) in [("raw", raw_impls()), ("cooked", cooked_impls())].iter() {
+        let reference = (impls[0].1)(&case.input);
+        for (i, &(name, render)) in impls.iter().enumerate() {
+            let (section, output) = match i {
+                0 => (kind.to_string(), reference.clone()),
+                _ => (format!("{} {}", kind, name), render(&case.input)),
+            };
+            let expected = case.expected(&section).or_else(|| case.expected(kind));
+            if bless {
+                if i == 0 || output != reference {
+                    blessed.push((section, output));
+                }
+            } else if expected != Some(&output) {
+                let expected = match expected {
+                    Some(lines) => lines.join("\n        "),
+                    None => "nothing; run with BLESS=1".to_string(),
+                };
+                failures.push(format!(
+                    "{} {}:\n    expected:\n        {}\n    found:\n        {}",
+                    kind,
+                    name,
+                    expected,
+                    output.join("\n        "),
+                ));
+            }
+        }
+    }
+    if bless {
+        // Keep what implementations not built now are expected to produce.
+        for (section, lines) in case.expected.drain(..) {
+            if section.contains(' ') && !blessed.iter().any(|(name, _)| *name == section) {
+                let built = raw_impls()
+                    .iter()
+                    .chain(&cooked_impls())
+                    .any(|(name, _)| section.ends_with(name));
+                if !built {
+                    blessed.push((section, lines));
+                }
+            }
+        }
+        case.expected = blessed;
+    }
+    failures
+}
+
+#[test]
+fn cases() {
+    let bless = env::var_os("BLESS").is_some();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cases");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("txt".as_ref()))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no cases in {}", dir.display());
+
+    let mut failures = vec![];
+    for path in paths {
+        let text = fs::read_to_string(&path).unwrap();
+        let mut cases = parse(&path, &text);
+        for case in &mut cases {
+            for failure in check(case, bless) {
+                failures.push(format!(
+                    "{}:{} ({}): {}",
+                    path.display(),
+                    case.line,
+                    case.description,
+                    failure,
+                ));
+            }
+        }
+        if bless {
+            let written = write(&cases);
+            if written != text {
+                fs::write(&path, written).unwrap();
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} failures; run with BLESS=1 to accept what each implementation produces:\n\n{}",
+        failures.len(),
+        failures.join("\n\n"),
+    );
+
+    #[cfg(feature = "coverage")]
+    every_rule_is_applied();
+}
+
+#[cfg(feature = "coverage")]
+fn every_rule_is_applied() {
+    use lexical_specification::coverage;
+
+    let hits = coverage::hits();
+    let uncovered: Vec<&str> = coverage::rules()
+        .into_iter()
+        .filter(|id| !hits.contains(id))
+        .collect();
+    assert!(
+        uncovered.is_empty(),
+        "{} rules are applied by no case; add cases to tests/cases which apply:\n    {}",
+        uncovered.len(),
+        uncovered.join("\n    "),
+    );
+}
@@ -0,0 +1,31 @@
+// Each test crate uses only some of these.
+#![allow(dead_code, unused_macros)]
+
+use lexical_specification::{cooked, raw};
+
+pub type ReferenceCooked =
+    cooked::Pipeline<raw::reference_impl::Lexer, cooked::reference_impl::Cooker>;
+
+/// Asserts that raw lexers `A` and `B` agree on `text`,
+/// or panics with a minimal text on which they disagree, as a case for `tests/cases`.
+macro_rules! assert_raw_lexers_agree {
+    ($A:path, $B:path, $text:expr) => {{
+        use lexical_specification::{minimize, raw::Lexer as _};
+        let text: &str = $text;
+        if let Some(minimal) = minimize::raw_disagreement::<$A, $B>(text) {
+            panic!(
+                "{} and {} disagree on {:?}, and minimally on {:?}:\n    {}: {:?}\n    {}: {:?}\n\
+                 Add it to tests/cases and run with BLESS=1:\n{}",
+                stringify!($A),
+                stringify!($B),
+                text,
+                minimal,
+                stringify!($A),
+                <$A>::lex_all_recovering(&minimal),
+                stringify!($B),
+                <$B>::lex_all_recovering(&minimal),
+                minimize::test_case(&minimal),
+            );
+        }
+    }};
+}
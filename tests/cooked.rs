@@ -0,0 +1,109 @@
+use lexical_specification::{
+    cooked::{self, reference_impl::Cooker, Cooker as _},
+    raw::{self, Lexer as _},
+};
+
+fn cook(text: &str) -> Result<Vec<cooked::Token>, cooked::CookError> {
+    let raw = raw::reference_impl::Lexer::lex_all(text).unwrap();
+    Cooker::cook_all(text, &raw)
+}
+
+#[test]
+fn spans_cover_raw_tokens() {
+    let src = "x = 1.5e3f32 + 0b1.foo;";
+    let spans: Vec<_> = cooked::Token::spanned(src, cook(src).unwrap())
+        .map(|token| (token.class, token.span(), token.text))
+        .filter(|&(class, ..)| class != cooked::Class::Trivia_Whitespace)
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            (cooked::Class::Identifier_Plain, 0..1, "x"),
+            (cooked::Class::Punctuation_Equal, 2..3, "="),
+            (cooked::Class::Literal_Float_Suffixed, 4..12, "1.5e3f32"),
+            (cooked::Class::Punctuation_Plus, 13..14, "+"),
+            (
+                cooked::Class::Literal_Integer_Binary_Unsuffixed,
+                15..18,
+                "0b1"
+            ),
+            (cooked::Class::Punctuation_Dot, 18..19, "."),
+            (cooked::Class::Identifier_Plain, 19..22, "foo"),
+            (cooked::Class::Punctuation_Semicolon, 22..23, ";"),
+        ]
+    );
+}
+
+#[test]
+fn class_table() {
+    for (ix, &class) in cooked::Class::ALL.iter().enumerate() {
+        assert_eq!(class as usize, ix, "{:?} is out of place", class);
+        assert_eq!(class.to_string(), format!("{:?}", class).replace('_', "::"));
+    }
+}
+
+#[test]
+fn errors() {
+    let src = "x = 0b102 + y;";
+    let raw = raw::reference_impl::Lexer::lex_all(src).unwrap();
+    let err = Cooker::cook_all(src, &raw).unwrap_err();
+    assert_eq!(err.kind, cooked::InvalidBinaryDigit);
+    assert_eq!(&src[err.span.clone()], "0b102");
+    assert_eq!(
+        err.to_string(),
+        "Invalid digit for binary integer literal at bytes 4..9"
+    );
+
+    // No raw lexer produces this binary integer, so no rule cooks it.
+    let src = "x = 0b1x;";
+    let raw = [
+        (raw::identifier, 1),
+        (raw::whitespace, 1),
+        (raw::equal, 1),
+        (raw::whitespace, 1),
+        (raw::binary_integer, 4),
+        (raw::semicolon, 1),
+    ];
+    let raw: Vec<_> = raw
+        .iter()
+        .map(|&(class, length)| raw::Token { class, length })
+        .collect();
+    let err = Cooker::cook_all(src, &raw).unwrap_err();
+    assert_eq!(err, cooked::CookError::new(cooked::NoMatchingCase, 4..8));
+}
+
+#[test]
+fn class_hierarchy() {
+    use cooked::{Base, DocStyle, LiteralKind};
+    for &class in cooked::Class::ALL.iter() {
+        let name = format!("{:?}", class);
+        assert_eq!(class.is_literal(), name.starts_with("Literal_"), "{}", name);
+        assert_eq!(class.is_suffixed(), name.ends_with("_Suffixed"), "{}", name);
+        assert_eq!(
+            class.is_doc(),
+            name.starts_with("Documentation_"),
+            "{}",
+            name
+        );
+        assert_eq!(class.is_trivia(), name.starts_with("Trivia_"), "{}", name);
+        if let Some(kind) = class.literal_kind() {
+            let kind = match kind {
+                LiteralKind::Integer(base) => format!("Integer_{:?}", base),
+                kind => format!("{:?}", kind),
+            };
+            assert!(name.starts_with(&format!("Literal_{}_", kind)), "{}", name);
+        }
+        if let Some(style) = class.doc_style() {
+            assert!(name.starts_with(&format!("Documentation_{:?}_", style)));
+        }
+    }
+    assert_eq!(
+        cooked::Literal_Integer_Hexadecimal_Unsuffixed.literal_kind(),
+        Some(LiteralKind::Integer(Base::Hexadecimal))
+    );
+    assert_eq!(
+        cooked::Documentation_Outer_Line.doc_style(),
+        Some(DocStyle::Outer)
+    );
+    assert_eq!(cooked::Trivia_Comment_Line.doc_style(), None);
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "logos")]
+
+use {
+    lexical_specification::{
+        minimize,
+        raw::{self, reference_impl},
+    },
+    std::env,
+};
+
+/// Characters chosen to reach the edges of the raw classes:
+/// number prefixes and exponents, quotes, raw string fences, comment delimiters,
+/// escapes, line endings, a non-ASCII XID_Continue character that is not XID_Start,
+/// and a non-ASCII character that starts no token.
+const ALPHABET: &str = "0boxe._'\"r#/*\\\n\r·🦀";
+const MAX_LENGTH: usize = 4;
+
+/// The alphabet, from `EXHAUSTIVE_ALPHABET` if set.
+fn alphabet() -> Vec<char> {
+    let alphabet = env::var("EXHAUSTIVE_ALPHABET").unwrap_or_else(|_| ALPHABET.to_string());
+    let mut alphabet: Vec<char> = alphabet.chars().collect();
+    alphabet.sort();
+    alphabet.dedup();
+    alphabet
+}
+
+/// The maximum length in characters, from `EXHAUSTIVE_MAX_LENGTH` if set.
+fn max_length() -> usize {
+    env::var("EXHAUSTIVE_MAX_LENGTH").map_or(MAX_LENGTH, |n| {
+        n.parse()
+            .expect("EXHAUSTIVE_MAX_LENGTH is not a nonnegative integer")
+    })
+}
+
+/// Calls `f` with every string of up to `max_length` characters of `alphabet`,
+/// shortest first.
+fn for_each_string(alphabet: &[char], max_length: usize, mut f: impl FnMut(&str)) {
+    let mut digits: Vec<usize> = vec![];
+    let mut s = String::new();
+    loop {
+        s.clear();
+        s.extend(digits.iter().map(|&digit| alphabet[digit]));
+        f(&s);
+        // Count up, as if `digits` were a number in base `alphabet.len()`.
+        match digits.iter().rposition(|&digit| digit + 1 < alphabet.len()) {
+            Some(at) => {
+                digits[at] += 1;
+                digits[at + 1..].fill(0);
+            }
+            None if digits.len() < max_length && !alphabet.is_empty() => {
+                digits.fill(0);
+                digits.push(0);
+            }
+            None => return,
+        }
+    }
+}
+
+/// The reference and logos lexers agree on every string over the alphabet,
+/// up to the maximum length.
+///
+/// Run with e.g. `EXHAUSTIVE_MAX_LENGTH=5 cargo test --release --features logos --test exhaustive`
+/// for a more thorough check than CI's.
+#[test]
+fn raw_lexers_agree_on_all_short_strings() {
+    let alphabet = alphabet();
+    let max_length = max_length();
+    let mut checked = 0;
+    let mut disagreements = 0;
+    let mut minimal = vec![];
+    for_each_string(&alphabet, max_length, |s| {
+        checked += 1;
+        let disagreement =
+            minimize::raw_disagreement::<reference_impl::Lexer, raw::logos_impl::Lexer>(s);
+        if let Some(disagreement) = disagreement {
+            disagreements += 1;
+            if !minimal.contains(&disagreement) {
+                minimal.push(disagreement);
+            }
+        }
+    });
+    assert!(
+        disagreements == 0,
+        "raw lexers disagree on {} of {} strings of up to {} of {:?}, minimally on {:?}",
+        disagreements,
+        checked,
+        max_length,
+        alphabet.iter().collect::<String>(),
+        minimal,
+    );
+}
@@ -0,0 +1,130 @@
+use lexical_specification::{cooked, explain, raw};
+
+#[test]
+fn raw_tokens_name_their_rules() {
+    let explained = explain::raw("0.0b·");
+    let rules: Vec<_> = explained
+        .iter()
+        .map(|e| {
+            let decision = e.decision.as_ref().unwrap();
+            (
+                e.token.class.unwrap(),
+                &decision.matches[..],
+                decision.rule,
+                e.recognizer,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            (
+                raw::decimal_integer,
+                &[raw::decimal_integer][..],
+                Some("tie.single"),
+                None
+            ),
+            (raw::dot, &[raw::dot], Some("tie.single"), None),
+            (
+                raw::binary_integer,
+                &[raw::binary_integer, raw::decimal_integer],
+                Some("tie.binary_integer"),
+                None
+            ),
+            (
+                raw::identifier_fragment,
+                &[raw::identifier_fragment],
+                Some("tie.single"),
+                None
+            ),
+        ]
+    );
+    assert_eq!(
+        explained[2].to_string(),
+        "binary_integer \"0b\" at 2..4, matched by binary_integer decimal_integer, \
+         by tie.binary_integer",
+    );
+}
+
+#[test]
+fn raw_errors_name_their_recognizer() {
+    let explained = explain::raw("r#·\\");
+    let (fence, backslash) = (&explained[0], &explained[2]);
+    assert_eq!(fence.token.text, "r#");
+    assert_eq!(
+        fence.decision.as_ref().unwrap().rule,
+        Some("tie.raw_string")
+    );
+    assert_eq!(fence.recognizer, Some("parse_raw_string"));
+    assert_eq!(
+        fence.token.class,
+        Err(raw::LexError::new(raw::InvalidRawStringFence, 2))
+    );
+    let decision = backslash.decision.as_ref().unwrap();
+    assert_eq!(decision.matches, []);
+    assert_eq!(decision.rule, Some("lex.unknown_start"));
+    assert_eq!(
+        backslash.to_string(),
+        "error \"\\\\\" at 4..5, matched by no class, by lex.unknown_start: \
+         unknown start of token at byte 4",
+    );
+
+    let explained = explain::raw("b\"x");
+    assert_eq!(explained.len(), 1);
+    assert_eq!(explained[0].decision, None);
+    assert_eq!(
+        explained[0].token.class,
+        Err(raw::LexError::new(raw::UnterminatedByteString, 3))
+    );
+}
+
+#[test]
+fn cooked_tokens_name_their_rules() {
+    let explained = explain::cooked("0.0b· 1..x").unwrap();
+    let rules: Vec<_> = explained
+        .iter()
+        .map(|e| (e.token.as_ref().unwrap().text, e.rule.unwrap()))
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            (
+                "0.0b·",
+                "cooked.decimal_integer-dot-binary_integer-identifier_fragment"
+            ),
+            (" ", "cooked.whitespace"),
+            ("1", "cooked.decimal_integer-dot-dot"),
+            (".", "cooked.decimal_integer-dot-dot"),
+            (".", "cooked.decimal_integer-dot-dot"),
+            ("x", "cooked.identifier"),
+        ]
+    );
+    assert_eq!(
+        explained[5].to_string(),
+        "Identifier::Plain \"x\" at 10..11, by cooked.identifier",
+    );
+}
+
+#[test]
+fn cooked_errors_name_their_rule() {
+    let explained = explain::cooked("x 0b2").unwrap();
+    assert_eq!(explained.len(), 3);
+    assert_eq!(
+        explained[2].rule,
+        Some("cooked.binary_integer.invalid_digit")
+    );
+    assert_eq!(
+        explained[2].token,
+        Err(cooked::CookError::new(cooked::InvalidBinaryDigit, 2..5))
+    );
+    assert_eq!(
+        explained[2].to_string(),
+        "ERROR Invalid digit for binary integer literal at bytes 2..5, \
+         by cooked.binary_integer.invalid_digit",
+    );
+
+    assert_eq!(
+        explain::cooked("x \"").unwrap_err(),
+        raw::LexError::new(raw::UnknownStartOfToken, 2)
+    );
+}
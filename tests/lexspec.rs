@@ -0,0 +1,99 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs the `lexspec` binary on `src` as standard input,
+/// returning whether it succeeded and what it printed.
+fn lexspec(args: &[&str], src: &str) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lexspec"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(src.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn table() {
+    let (ok, out) = lexspec(&[], "r\"\t\" \\");
+    assert!(!ok);
+    assert_eq!(
+        out,
+        "0..4  raw_string  \"r\\\"\\t\\\"\"\n\
+         4..5  whitespace  \" \"\n\
+         5..6  error       \"\\\\\"  unknown start of token\n",
+    );
+}
+
+#[test]
+fn json_lines() {
+    let (ok, out) = lexspec(&["--cook", "--format", "json", "-"], "1.0f32 \"\u{1}\"");
+    assert!(ok);
+    assert_eq!(
+        out,
+        "{\"start\":0,\"end\":6,\"class\":\"Literal::Float::Suffixed\",\"text\":\"1.0f32\",\"raw_tokens\":4}\n\
+         {\"start\":6,\"end\":7,\"class\":\"Trivia::Whitespace\",\"text\":\" \",\"raw_tokens\":1}\n\
+         {\"start\":7,\"end\":10,\"class\":\"Literal::String::Unsuffixed\",\"text\":\"\\\"\\u0001\\\"\",\"raw_tokens\":1}\n",
+    );
+}
+
+#[test]
+fn debug() {
+    let (ok, out) = lexspec(&["--format", "debug"], "'a");
+    assert!(ok);
+    assert_eq!(
+        out,
+        "SpannedToken { class: lifetime, start: 0, text: \"'a\" }\n"
+    );
+}
+
+#[test]
+fn cooking_errors_fail() {
+    assert_eq!(lexspec(&["--cook"], "0b2"), (false, String::new()));
+}
+
+#[test]
+#[cfg(feature = "logos")]
+fn logos() {
+    assert_eq!(
+        lexspec(&["--lexer", "logos"], "fn"),
+        (true, "0..2  identifier  \"fn\"\n".to_string()),
+    );
+}
+
+#[test]
+fn explain() {
+    let (ok, out) = lexspec(&["--explain"], "0b");
+    assert!(ok);
+    assert_eq!(
+        out,
+        "binary_integer \"0b\" at 0..2, matched by binary_integer decimal_integer, \
+         by tie.binary_integer\n",
+    );
+    let (ok, out) = lexspec(&["--explain", "--cook"], "x 0b2");
+    assert!(!ok);
+    assert_eq!(
+        out,
+        "Identifier::Plain \"x\" at 0..1, by cooked.identifier\n\
+         Trivia::Whitespace \" \" at 1..2, by cooked.whitespace\n\
+         ERROR Invalid digit for binary integer literal at bytes 2..5, \
+         by cooked.binary_integer.invalid_digit\n",
+    );
+    assert_eq!(
+        lexspec(&["--explain", "--format", "json"], "x"),
+        (false, String::new()),
+    );
+}
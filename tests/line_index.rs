@@ -0,0 +1,98 @@
+use lexical_specification::{
+    line_index::{Encoding, LineCol, LineIndex},
+    raw::{self, reference_impl, Lexer as _},
+};
+
+const SRC: &str = "fn main() {\r\n    let ü = \"a\r\nb\"; // 🦀\r\n    let x = 1;\rlet y = 2;\n}";
+
+#[test]
+fn token_positions() {
+    let index = LineIndex::new(SRC);
+    let positions: Vec<_> = reference_impl::Lexer::tokenize(SRC)
+        .spanned()
+        .map(Result::unwrap)
+        .filter(|token| token.class == raw::identifier)
+        .map(|token| {
+            let utf8 = index.line_col(token.start, Encoding::Utf8).unwrap();
+            let utf16 = index.line_col(token.start, Encoding::Utf16).unwrap();
+            let char = index.line_col(token.start, Encoding::Char).unwrap();
+            (token.text, utf8.line, utf8.col, utf16.col, char.col)
+        })
+        .collect();
+    assert_eq!(
+        positions,
+        [
+            ("fn", 0, 0, 0, 0),
+            ("main", 0, 3, 3, 3),
+            ("let", 1, 4, 4, 4),
+            ("ü", 1, 8, 8, 8),
+            ("let", 3, 4, 4, 4),
+            ("x", 3, 8, 8, 8),
+            // A bare CR does not end a line.
+            ("let", 3, 15, 15, 15),
+            ("y", 3, 19, 19, 19),
+        ]
+    );
+
+    // The line comment ends before the CR of its CRLF.
+    let comment = reference_impl::Lexer::tokenize(SRC)
+        .spanned()
+        .map(Result::unwrap)
+        .find(|token| token.class == raw::line_comment)
+        .unwrap();
+    assert_eq!(comment.text, "// 🦀\r");
+    let end = comment.end() - 1;
+    assert_eq!(
+        index.line_col(end, Encoding::Utf8),
+        Some(LineCol { line: 2, col: 11 })
+    );
+    assert_eq!(
+        index.line_col(end, Encoding::Utf16),
+        Some(LineCol { line: 2, col: 9 })
+    );
+    assert_eq!(
+        index.line_col(end, Encoding::Char),
+        Some(LineCol { line: 2, col: 8 })
+    );
+}
+
+#[test]
+fn round_trip() {
+    let index = LineIndex::new(SRC);
+    assert_eq!(index.line_count(), 5);
+    for encoding in [Encoding::Utf8, Encoding::Utf16, Encoding::Char]
+        .iter()
+        .copied()
+    {
+        for offset in 0..=SRC.len() {
+            match index.line_col(offset, encoding) {
+                Some(pos) => assert_eq!(index.offset(pos, encoding), Some(offset)),
+                None => assert!(!SRC.is_char_boundary(offset)),
+            }
+        }
+    }
+}
+
+#[test]
+fn invalid_positions() {
+    let index = LineIndex::new(SRC);
+    // Inside the 🦀, which is two UTF-16 code units wide.
+    assert_eq!(
+        index.offset(LineCol { line: 2, col: 8 }, Encoding::Utf16),
+        None
+    );
+    // Past the end of the line.
+    assert_eq!(
+        index.offset(LineCol { line: 2, col: 12 }, Encoding::Utf16),
+        None
+    );
+    assert_eq!(
+        index.offset(LineCol { line: 5, col: 0 }, Encoding::Utf8),
+        None
+    );
+    assert_eq!(
+        index.offset(LineCol { line: 4, col: 1 }, Encoding::Utf8),
+        Some(SRC.len())
+    );
+    assert_eq!(index.line_col(SRC.len() + 1, Encoding::Utf8), None);
+}
@@ -0,0 +1,98 @@
+use lexical_specification::{
+    cooked::{self, Lexer as _},
+    minimize::{self, minimize},
+    raw::{self, reference_impl},
+};
+
+#[macro_use]
+mod common;
+use common::ReferenceCooked;
+
+/// The reference lexer, but without lifetimes.
+struct NoLifetimes;
+
+impl raw::Lexer for NoLifetimes {
+    fn lex(s: &str) -> Result<raw::Token, raw::LexError> {
+        match reference_impl::Lexer::lex(s)? {
+            raw::Token {
+                class: raw::lifetime,
+                ..
+            } => Err(raw::LexError::new(raw::UnknownStartOfToken, 0)),
+            token => Ok(token),
+        }
+    }
+}
+
+#[test]
+fn minimizes_to_what_fails() {
+    let minimal = minimize("fn main() { let s = b\"unterminated; }", |s| {
+        s.contains("b\"") && s.ends_with('}')
+    });
+    assert_eq!(minimal, "b\"}");
+}
+
+#[test]
+fn minimal_is_1_minimal() {
+    let fails = |s: &str| s.matches('a').count() >= 2 && s.contains('z');
+    let minimal = minimize("banana split with a pizza", fails);
+    assert!(fails(&minimal));
+    for (at, c) in minimal.char_indices() {
+        let mut smaller = minimal.clone();
+        smaller.remove(at);
+        assert!(
+            !fails(&smaller),
+            "removing {:?} from {:?} still fails",
+            c,
+            minimal
+        );
+    }
+    assert_eq!(minimal.chars().count(), 3);
+}
+
+#[test]
+fn raw_disagreement() {
+    let src = "fn longest<'a>(x: &'a str, y: &'a str) -> &'a str { x }";
+    let minimal = minimize::raw_disagreement::<reference_impl::Lexer, NoLifetimes>(src);
+    assert_eq!(minimal.as_deref(), Some("'a"));
+    assert_eq!(minimize::test_case("'a"), "===\n--- input escaped\n'a\n");
+
+    let agreeing = minimize::raw_disagreement::<reference_impl::Lexer, NoLifetimes>("'a'");
+    assert_eq!(agreeing, None);
+}
+
+#[test]
+fn cooked_disagreement() {
+    let minimal = minimize::cooked_disagreement::<
+        ReferenceCooked,
+        cooked::Pipeline<NoLifetimes, cooked::reference_impl::Cooker>,
+    >("impl<'a> Iterator for Tokens<'a> {}");
+    assert_eq!(minimal.as_deref(), Some("'a"));
+}
+
+#[test]
+fn test_cases_escape_as_string_literals() {
+    for text in &["'a", "b\"", "\r\n\t\\", "\u{0}🦀\u{301}"] {
+        let case = minimize::test_case(text);
+        let escaped = case.lines().nth(2).unwrap();
+        let entry = format!("\"{}\",", escaped);
+        let classes: Vec<_> = ReferenceCooked::lex_all(&entry)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.class)
+            .collect();
+        assert_eq!(
+            classes,
+            [cooked::Literal_String_Unsuffixed, cooked::Punctuation_Comma],
+            "{} is not the escapes of a string literal",
+            entry,
+        );
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "Add it to tests/cases and run with BLESS=1:\n===\n--- input escaped\n'a\n"
+)]
+fn assert_raw_lexers_agree_shows_case() {
+    assert_raw_lexers_agree!(reference_impl::Lexer, NoLifetimes, "struct S<'a>(&'a ());");
+}
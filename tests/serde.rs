@@ -0,0 +1,62 @@
+#![cfg(feature = "serde")]
+
+use lexical_specification::{
+    cooked::{self, Lexer as _},
+    raw::{self, reference_impl, Lexer as _},
+};
+
+#[test]
+fn raw_tokens_use_spec_names() {
+    let tokens = reference_impl::Lexer::lex_all("br\"x\" 'a").unwrap();
+    let json = serde_json::to_string(&tokens).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"class":"raw_byte_string","length":5},{"class":"whitespace","length":1},{"class":"lifetime","length":2}]"#,
+    );
+    assert_eq!(
+        serde_json::from_str::<Vec<raw::Token>>(&json).unwrap(),
+        tokens
+    );
+}
+
+#[test]
+fn cooked_tokens_use_spec_names() {
+    let tokens =
+        cooked::Pipeline::<reference_impl::Lexer, cooked::reference_impl::Cooker>::lex_all(
+            "1.0f32",
+        )
+        .unwrap();
+    let json = serde_json::to_string(&tokens).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"class":"Literal::Float::Suffixed","length":6,"raw_tokens":4}]"#,
+    );
+    assert_eq!(
+        serde_json::from_str::<Vec<cooked::Token>>(&json).unwrap(),
+        tokens
+    );
+}
+
+#[test]
+fn every_class_round_trips() {
+    for &class in raw::Class::ALL.iter() {
+        let json = serde_json::to_string(&class).unwrap();
+        assert_eq!(json, format!("{:?}", format!("{:?}", class)));
+        assert_eq!(serde_json::from_str::<raw::Class>(&json).unwrap(), class);
+    }
+    for &class in cooked::Class::ALL.iter() {
+        let json = serde_json::to_string(&class).unwrap();
+        assert_eq!(json, format!("{:?}", class.to_string()));
+        assert_eq!(serde_json::from_str::<cooked::Class>(&json).unwrap(), class);
+    }
+}
+
+#[test]
+fn unknown_classes_fail() {
+    assert!(serde_json::from_str::<raw::Class>(r#""Identifier""#).is_err());
+    let err = serde_json::from_str::<cooked::Class>(r#""Literal_Float_Suffixed""#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"no cooked class "Literal_Float_Suffixed""#
+    );
+}
@@ -0,0 +1,273 @@
+use {
+    lexical_specification::{
+        cooked, coverage,
+        raw::{
+            self,
+            reference_impl::{self, REGEX_SET},
+        },
+    },
+    once_cell::sync::Lazy,
+    pretty_assertions::assert_eq,
+    regex::Regex,
+    regex_automata::{dense, DenseDFA, DFA},
+    std::{
+        collections::{HashSet, VecDeque},
+        env, fs,
+    },
+};
+
+/// `spec/raw.md` with the sections build.rs generates from `spec/raw-classes.txt` filled in.
+const RAW_SPEC: &str = include_str!(concat!(env!("OUT_DIR"), "/raw.md"));
+const RAW_REFERENCE_IMPL: &str = include_str!("../src/raw/reference_impl.rs");
+
+fn spec(name: &str) -> String {
+    fs::read_to_string(format!("{}/spec/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
+
+fn unescape_html(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+struct Definition {
+    /// The `id` of the `<dt>`, if any.
+    id: Option<String>,
+    term: String,
+    /// The text after `where` following the term, with code in backticks.
+    condition: Option<String>,
+    definition: String,
+    /// Any parenthesized note after the `<dd>`'s `<code>`.
+    note: Option<String>,
+}
+
+/// Each `<dt>` and `<dd>` pair of `spec` that start with a `<code>`,
+/// with the contents of the first `<code>` of each and any condition on the term.
+fn definitions(spec: &str) -> Vec<Definition> {
+    static DEFINITION: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r#"(?s)^(?: id="([^"]*)")?><code>([^<]*)</code>(.*?)</dt>\s*<dd><code>([^<]*)</code>\s*(?:\((.*?)\))?\s*</dd>"#,
+        )
+        .unwrap()
+    });
+    spec.split("<dt")
+        .skip(1)
+        .filter_map(|definition| DEFINITION.captures(definition))
+        .map(|captures| Definition {
+            id: captures.get(1).map(|id| id.as_str().to_string()),
+            term: unescape_html(&captures[2]),
+            condition: condition(&captures[3]),
+            definition: unescape_html(&captures[4]),
+            note: captures.get(5).map(|note| note.as_str().to_string()),
+        })
+        .collect()
+}
+
+/// The condition in `rest` of a `<dt>` after its term, if it has one.
+fn condition(rest: &str) -> Option<String> {
+    let condition = rest.trim_start().strip_prefix("where ")?;
+    let condition = condition.replace("<code>", "`").replace("</code>", "`");
+    Some(unescape_html(
+        &condition.split_whitespace().collect::<Vec<_>>().join(" "),
+    ))
+}
+
+/// The `id` of each `<dt>` of `spec` that has one.
+fn ids(spec: &str) -> Vec<&str> {
+    spec.split("<dt id=\"")
+        .skip(1)
+        .map(|rest| &rest[..rest.find('"').unwrap()])
+        .collect()
+}
+
+/// The body of each fenced block of `language` in `spec`.
+fn fenced<'a>(spec: &'a str, language: &str) -> Vec<&'a str> {
+    let open = format!("```{}\n", language);
+    spec.match_indices(&open)
+        .map(|(at, _)| {
+            let body = &spec[at + open.len()..];
+            &body[..body.find("```").unwrap()]
+        })
+        .collect()
+}
+
+/// Replaces each `(?&name)` in `regex` with the subpattern of that name
+/// from the spec's `(?(DEFINE)...)` block.
+fn inline(regex: &str, spec: &str) -> String {
+    static SUBPATTERN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\(\?P<(\w+)>\s+(.*?)\s+\)").unwrap());
+    let define = fenced(spec, "regex")[0];
+    let mut regex = regex.to_string();
+    while let Some(at) = regex.find("(?&") {
+        let end = at + regex[at..].find(')').unwrap();
+        let name = &regex[at + 3..end];
+        let subpattern = define
+            .lines()
+            .filter_map(|line| SUBPATTERN.captures(line))
+            .find(|captures| &captures[1] == name)
+            .unwrap_or_else(|| panic!("undefined subpattern {:?}", name));
+        regex.replace_range(at..=end, &format!("(?:{})", &subpattern[2]));
+    }
+    regex
+}
+
+/// The shortest string in exactly one of the languages of `a` and `b`, if any.
+fn distinguish(a: &str, b: &str) -> Option<String> {
+    let dfa = |regex: &str| -> DenseDFA<Vec<usize>, usize> {
+        dense::Builder::new()
+            .anchored(true)
+            .longest_match(true)
+            .build(regex)
+            .unwrap()
+    };
+    let (a, b) = (dfa(a), dfa(b));
+    let start = (a.start_state(), b.start_state());
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start);
+    queue.push_back((start, vec![]));
+    while let Some(((a_state, b_state), text)) = queue.pop_front() {
+        if a.is_match_state(a_state) != b.is_match_state(b_state) {
+            return Some(String::from_utf8_lossy(&text).into_owned());
+        }
+        for byte in 0..=u8::MAX {
+            let next = (a.next_state(a_state, byte), b.next_state(b_state, byte));
+            if !(a.is_dead_state(next.0) && b.is_dead_state(next.1)) && seen.insert(next) {
+                let mut text = text.clone();
+                text.push(byte);
+                queue.push_back((next, text));
+            }
+        }
+    }
+    None
+}
+
+/// The source of the function `name` in `source`, from its signature to its closing brace.
+fn function<'a>(source: &'a str, name: &str) -> Option<&'a str> {
+    let start = source.find(&format!("fn {}(", name))?;
+    let start = source[..start].rfind('\n').map_or(0, |at| at + 1);
+    let end = start + source[start..].find("\n}\n")? + 3;
+    Some(&source[start..end])
+}
+
+#[test]
+fn raw_spec_is_up_to_date() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/spec/raw.md");
+    if fs::read_to_string(path).unwrap() == RAW_SPEC {
+        return;
+    }
+    if env::var_os("UPDATE_SPEC").is_some() {
+        fs::write(path, RAW_SPEC).unwrap();
+    } else {
+        panic!("spec/raw.md is out of date with spec/raw-classes.txt; rerun with UPDATE_SPEC=1");
+    }
+}
+
+#[test]
+fn raw_spec_regexes_match_reference_impl() {
+    let spec = spec("raw.md");
+    let in_spec: Vec<String> = definitions(&spec)
+        .into_iter()
+        .map(|definition| format!("{}: {}", definition.term, definition.definition))
+        .collect();
+    // The spec's regex where it is equivalent to the reference's, so only differences show.
+    let in_reference_impl: Vec<String> = raw::Class::ALL
+        .iter()
+        .zip(REGEX_SET.patterns())
+        .map(|(class, pattern)| {
+            let pattern = pattern.trim_start_matches(r"\A");
+            let regex = definitions(&spec)
+                .into_iter()
+                .find(|definition| definition.term == format!("{:?}", class))
+                .map(|definition| definition.definition);
+            match regex {
+                Some(regex) => match distinguish(&inline(&regex, &spec), pattern) {
+                    None => format!("{:?}: {}", class, regex),
+                    Some(text) => format!("{:?}: {} (differs on {:?})", class, pattern, text),
+                },
+                None => format!("{:?}: {}", class, pattern),
+            }
+        })
+        .collect();
+    assert_eq!(in_spec, in_reference_impl);
+}
+
+#[test]
+fn raw_spec_recognizers_match_reference_impl() {
+    let spec = spec("raw.md");
+    let recognizers = fenced(&spec, "rust");
+    assert!(!recognizers.is_empty(), "no recognizers in spec/raw.md");
+    for recognizer in recognizers {
+        let name = recognizer
+            .split("fn ")
+            .nth(1)
+            .and_then(|signature| signature.split('(').next())
+            .unwrap();
+        assert_eq!(
+            recognizer,
+            function(RAW_REFERENCE_IMPL, name).unwrap_or(""),
+            "spec/raw.md and reference_impl disagree on {}",
+            name,
+        );
+    }
+}
+
+#[test]
+fn raw_spec_ties_match_reference_impl() {
+    let in_reference_impl: Vec<&str> = reference_impl::TIES.iter().map(|&(id, ..)| id).collect();
+    assert_eq!(ids(&spec("raw.md")), in_reference_impl);
+    for &(id, classes, preferred) in reference_impl::TIES {
+        assert_eq!(id, format!("tie.{:?}", preferred));
+        assert!(
+            classes.contains(&preferred),
+            "{} prefers a class it does not tie",
+            id
+        );
+    }
+}
+
+#[test]
+fn cooked_spec_rules_match_reference_impl() {
+    let in_spec: Vec<String> = definitions(&spec("cooked.md"))
+        .into_iter()
+        .map(|definition| {
+            let id = definition.id.unwrap_or_default();
+            let raw = match definition.condition {
+                Some(condition) => format!("{} where {}", definition.term, condition),
+                None => definition.term,
+            };
+            let cooked = definition.definition;
+            match definition.note {
+                Some(note) if cooked == "ERROR" => format!("{}: {} => ERROR ({})", id, raw, note),
+                _ => format!("{}: {} => {}", id, raw, cooked),
+            }
+        })
+        .collect();
+    let in_reference_impl: Vec<String> = cooked::reference_impl::rules()
+        .map(|(id, raw, condition, cooked)| {
+            let raw: Vec<String> = raw.iter().map(|class| format!("{:?}", class)).collect();
+            let raw = match condition {
+                Some(condition) => format!("{} where {}", raw.join(" "), condition),
+                None => raw.join(" "),
+            };
+            let cooked = match cooked {
+                Ok(classes) => classes
+                    .iter()
+                    .map(|class| class.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                Err(kind) => format!("ERROR ({})", kind),
+            };
+            format!("{}: {} => {}", id, raw, cooked)
+        })
+        .collect();
+    assert_eq!(in_spec, in_reference_impl);
+}
+
+#[test]
+fn rule_ids_are_unique() {
+    let mut seen = HashSet::new();
+    for id in coverage::rules() {
+        assert!(seen.insert(id), "two rules are {}", id);
+    }
+}
@@ -0,0 +1,181 @@
+//! Analyses of the raw lexer's regular expressions as DFAs,
+//! so unlike testing sample inputs they cover every possible input.
+
+use {
+    lexical_specification::raw::{
+        reference_impl::{self, REGEX_SET},
+        Class, LexError, Lexer as _, UnresolvedTie,
+    },
+    once_cell::sync::Lazy,
+    regex_automata::{dense, DenseDFA, DFA},
+    std::collections::{BTreeMap, HashSet, VecDeque},
+};
+
+type Dfa = DenseDFA<Vec<usize>, usize>;
+
+/// The anchored DFA of each class, in the order of `Class::ALL`.
+static DFAS: Lazy<Vec<Dfa>> = Lazy::new(|| {
+    REGEX_SET
+        .patterns()
+        .iter()
+        .map(|pattern| {
+            dense::Builder::new()
+                .anchored(true)
+                .longest_match(true)
+                .build(pattern.trim_start_matches(r"\A"))
+                .unwrap()
+        })
+        .collect()
+});
+
+/// Every set of classes whose regular expressions can all match a prefix of one source,
+/// each with the shortest such source.
+///
+/// Walks the product of the classes' DFAs byte by byte.
+fn match_sets() -> Vec<(Vec<Class>, String)> {
+    // The live state of each DFA, and the bit set of classes that have matched a prefix.
+    type State = (Vec<(usize, usize)>, u64);
+    let start: State = (
+        DFAS.iter()
+            .enumerate()
+            .map(|(class, dfa)| (class, dfa.start_state()))
+            .collect(),
+        0,
+    );
+    let mut seen: HashSet<State> = HashSet::new();
+    let mut queue: VecDeque<(State, Vec<u8>)> = VecDeque::new();
+    let mut sets: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+    seen.insert(start.clone());
+    queue.push_back((start, vec![]));
+
+    while let Some(((live, matched), prefix)) = queue.pop_front() {
+        sets.entry(matched).or_insert_with(|| prefix.clone());
+        for byte in 0..=u8::MAX {
+            let mut next = (vec![], matched);
+            for &(class, state) in &live {
+                let state = DFAS[class].next_state(state, byte);
+                if DFAS[class].is_match_state(state) {
+                    next.1 |= 1 << class;
+                }
+                if !DFAS[class].is_dead_state(state) {
+                    next.0.push((class, state));
+                }
+            }
+            if seen.insert(next.clone()) {
+                let mut prefix = prefix.clone();
+                prefix.push(byte);
+                queue.push_back((next, prefix));
+            }
+        }
+    }
+
+    sets.into_iter()
+        .map(|(matched, prefix)| {
+            let classes = Class::ALL
+                .iter()
+                .copied()
+                .filter(|&class| matched & 1 << class as usize != 0)
+                .collect();
+            // A set is first reached right after one of its classes matches, so at a char boundary.
+            (classes, String::from_utf8(prefix).unwrap())
+        })
+        .collect()
+}
+
+/// The shortest source matched by `shorter` that has a prefix matched by `longer`, if any.
+///
+/// If there is none, whenever both match, `longer` always matches more than `shorter`.
+fn no_longer(longer: Class, shorter: Class) -> Option<String> {
+    let (longer, shorter) = (&DFAS[longer as usize], &DFAS[shorter as usize]);
+    // The states of both DFAs, and whether `longer` has matched a prefix.
+    type State = (usize, usize, bool);
+    let start: State = (longer.start_state(), shorter.start_state(), false);
+    let mut seen: HashSet<State> = HashSet::new();
+    let mut queue: VecDeque<(State, Vec<u8>)> = VecDeque::new();
+    seen.insert(start);
+    queue.push_back((start, vec![]));
+
+    while let Some(((long, short, matched), prefix)) = queue.pop_front() {
+        for byte in 0..=u8::MAX {
+            let long = longer.next_state(long, byte);
+            let short = shorter.next_state(short, byte);
+            let matched = matched || longer.is_match_state(long);
+            let mut prefix = prefix.clone();
+            prefix.push(byte);
+            if matched && shorter.is_match_state(short) {
+                return Some(String::from_utf8_lossy(&prefix).into_owned());
+            }
+            let next = (long, short, matched);
+            let live = !shorter.is_dead_state(short) && (matched || !longer.is_dead_state(long));
+            if live && seen.insert(next) {
+                queue.push_back((next, prefix));
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn every_regex_set_match_is_handled() {
+    let mut ties = vec![];
+    for (classes, text) in match_sets() {
+        let matches: Vec<Class> = REGEX_SET
+            .matches(&text)
+            .iter()
+            .map(|ix| Class::ALL[ix])
+            .collect();
+        assert_eq!(
+            matches, classes,
+            "DFAs and regex set disagree on {:?}",
+            text
+        );
+        let lexed = reference_impl::Lexer::lex(&text);
+        assert!(
+            !matches!(
+                lexed,
+                Err(LexError {
+                    kind: UnresolvedTie,
+                    ..
+                })
+            ),
+            "no tie rule for {:?}, matched by {:?}",
+            classes,
+            text,
+        );
+        if classes.len() > 1 {
+            ties.push((classes, text, lexed));
+        }
+    }
+    insta::assert_debug_snapshot!(ties);
+}
+
+#[test]
+fn every_tie_prefers_the_longer_class() {
+    let mut problems = vec![];
+    for (classes, text) in match_sets() {
+        if classes.len() < 2 {
+            continue;
+        }
+        match reference_impl::tie(&classes) {
+            None => problems.push(format!(
+                "no tie rule for {:?}, all of which match {:?}",
+                classes, text,
+            )),
+            Some(preferred) => {
+                for &class in classes.iter().filter(|&&class| class != preferred) {
+                    if let Some(text) = no_longer(preferred, class) {
+                        problems.push(format!(
+                            "{:?} is preferred over {:?} but is no longer on {:?}",
+                            preferred, class, text,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    assert!(
+        problems.is_empty(),
+        "incomplete tie rules:\n{}",
+        problems.join("\n"),
+    );
+}